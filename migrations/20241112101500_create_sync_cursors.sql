@@ -0,0 +1,27 @@
+-- Add migration script here
+
+-- after_date only kept a timestamp that got truncated to a date before it was sent to nearblocks,
+-- sync_cursors keeps the exact block and receipt that was processed last per method family.
+CREATE TABLE IF NOT EXISTS
+  sync_cursors (
+    contract varchar NOT NULL,
+    method_family varchar NOT NULL,
+    block_height bigint NOT NULL,
+    block_timestamp bigint NOT NULL,
+    receipt_id varchar,
+    primary key (contract, method_family)
+  );
+
+INSERT INTO
+  sync_cursors (contract, method_family, block_height, block_timestamp, receipt_id)
+SELECT
+  'devhub.near',
+  'proposal',
+  0,
+  after_date,
+  NULL
+FROM
+  after_date
+LIMIT 1;
+
+DROP TABLE after_date;
@@ -0,0 +1,103 @@
+-- Add migration script here
+
+CREATE TABLE IF NOT EXISTS
+  communities (handle varchar PRIMARY KEY);
+
+-- Communities can be deleted on the contract, that is recorded as a snapshot with `deleted` set
+CREATE TABLE IF NOT EXISTS
+  community_snapshots (
+    handle varchar NOT NULL REFERENCES communities (handle),
+    block_height bigint NOT NULL,
+    ts bigint NOT NULL,
+    editor_id varchar NOT NULL,
+    deleted boolean NOT NULL DEFAULT FALSE,
+    name text,
+    tag varchar,
+    description text,
+    logo_url text,
+    banner_url text,
+    bio_markdown text,
+    github_handle varchar,
+    telegram_handle varchar,
+    twitter_handle varchar,
+    website_url text,
+    admins jsonb,
+    addons jsonb,
+    PRIMARY KEY (handle, ts)
+  );
+
+CREATE INDEX
+  idx_community_snapshots_handle ON community_snapshots (handle);
+
+CREATE INDEX
+  idx_community_snapshots_ts ON community_snapshots (ts);
+
+CREATE INDEX
+  idx_community_snapshots_admins ON community_snapshots USING GIN (admins);
+
+CREATE TABLE IF NOT EXISTS
+  addons (id varchar PRIMARY KEY);
+
+CREATE TABLE IF NOT EXISTS
+  addon_snapshots (
+    addon_id varchar NOT NULL REFERENCES addons (id),
+    block_height bigint NOT NULL,
+    ts bigint NOT NULL,
+    editor_id varchar NOT NULL,
+    title text,
+    description text,
+    icon text,
+    view_widget text,
+    configurator_widget text,
+    PRIMARY KEY (addon_id, ts)
+  );
+
+CREATE INDEX
+  idx_addon_snapshots_addon_id ON addon_snapshots (addon_id);
+
+-- Receipts of both the community and the addon methods
+CREATE TABLE IF NOT EXISTS
+  community_dumps (
+    receipt_id varchar PRIMARY KEY,
+    method_name varchar,
+    block_height bigint,
+    block_timestamp bigint,
+    args varchar,
+    author varchar,
+    handle varchar,
+    addon_id varchar
+  );
+
+CREATE VIEW
+  communities_with_latest_snapshot AS
+SELECT
+  cs.*
+FROM
+  community_snapshots cs
+  INNER JOIN (
+    SELECT
+      handle,
+      MAX(ts) AS max_ts
+    FROM
+      community_snapshots
+    GROUP BY
+      handle
+  ) latest_snapshots ON cs.handle = latest_snapshots.handle
+  AND cs.ts = latest_snapshots.max_ts;
+
+CREATE VIEW
+  addons_with_latest_snapshot AS
+SELECT
+  ads.*
+FROM
+  addon_snapshots ads
+  INNER JOIN (
+    SELECT
+      addon_id,
+      MAX(ts) AS max_ts
+    FROM
+      addon_snapshots
+    GROUP BY
+      addon_id
+  ) latest_snapshots ON ads.addon_id = latest_snapshots.addon_id
+  AND ads.ts = latest_snapshots.max_ts;
@@ -0,0 +1,20 @@
+-- Add migration script here
+
+-- Receipts the indexer couldn't process, kept with the full nearblocks transaction so they can be retried
+CREATE TABLE IF NOT EXISTS
+  failed_receipts (
+    receipt_id varchar PRIMARY KEY,
+    contract varchar NOT NULL,
+    method_family varchar NOT NULL,
+    method_name varchar,
+    block_height bigint NOT NULL,
+    block_timestamp bigint NOT NULL,
+    transaction jsonb NOT NULL,
+    error text NOT NULL,
+    attempts integer NOT NULL DEFAULT 1,
+    first_failed_at timestamptz NOT NULL DEFAULT now(),
+    last_failed_at timestamptz NOT NULL DEFAULT now()
+  );
+
+CREATE INDEX
+  idx_failed_receipts_method_family ON failed_receipts (contract, method_family);
@@ -0,0 +1,218 @@
+-- Everything we index is scoped by the account of the contract it came from, so one
+-- deployment can cache several DevHub instances. Existing rows were indexed from devhub.near.
+ALTER TABLE proposals
+ADD COLUMN contract VARCHAR NOT NULL DEFAULT 'devhub.near';
+
+ALTER TABLE proposal_snapshots
+ADD COLUMN contract VARCHAR NOT NULL DEFAULT 'devhub.near';
+
+ALTER TABLE dumps
+ADD COLUMN contract VARCHAR NOT NULL DEFAULT 'devhub.near';
+
+ALTER TABLE rfps
+ADD COLUMN contract VARCHAR NOT NULL DEFAULT 'devhub.near';
+
+ALTER TABLE rfp_snapshots
+ADD COLUMN contract VARCHAR NOT NULL DEFAULT 'devhub.near';
+
+ALTER TABLE rfp_dumps
+ADD COLUMN contract VARCHAR NOT NULL DEFAULT 'devhub.near';
+
+ALTER TABLE communities
+ADD COLUMN contract VARCHAR NOT NULL DEFAULT 'devhub.near';
+
+ALTER TABLE community_snapshots
+ADD COLUMN contract VARCHAR NOT NULL DEFAULT 'devhub.near';
+
+ALTER TABLE addons
+ADD COLUMN contract VARCHAR NOT NULL DEFAULT 'devhub.near';
+
+ALTER TABLE addon_snapshots
+ADD COLUMN contract VARCHAR NOT NULL DEFAULT 'devhub.near';
+
+ALTER TABLE community_dumps
+ADD COLUMN contract VARCHAR NOT NULL DEFAULT 'devhub.near';
+
+-- New rows always name their contract
+ALTER TABLE proposals ALTER COLUMN contract DROP DEFAULT;
+ALTER TABLE proposal_snapshots ALTER COLUMN contract DROP DEFAULT;
+ALTER TABLE dumps ALTER COLUMN contract DROP DEFAULT;
+ALTER TABLE rfps ALTER COLUMN contract DROP DEFAULT;
+ALTER TABLE rfp_snapshots ALTER COLUMN contract DROP DEFAULT;
+ALTER TABLE rfp_dumps ALTER COLUMN contract DROP DEFAULT;
+ALTER TABLE communities ALTER COLUMN contract DROP DEFAULT;
+ALTER TABLE community_snapshots ALTER COLUMN contract DROP DEFAULT;
+ALTER TABLE addons ALTER COLUMN contract DROP DEFAULT;
+ALTER TABLE addon_snapshots ALTER COLUMN contract DROP DEFAULT;
+ALTER TABLE community_dumps ALTER COLUMN contract DROP DEFAULT;
+
+-- Ids and handles are only unique within a contract
+ALTER TABLE rfp_snapshots DROP CONSTRAINT rfp_snapshots_rfp_id_fkey;
+ALTER TABLE community_snapshots DROP CONSTRAINT community_snapshots_handle_fkey;
+ALTER TABLE addon_snapshots DROP CONSTRAINT addon_snapshots_addon_id_fkey;
+
+ALTER TABLE proposals DROP CONSTRAINT proposals_pkey,
+ADD PRIMARY KEY (contract, id);
+
+ALTER TABLE proposal_snapshots DROP CONSTRAINT proposal_snapshots_pkey,
+ADD PRIMARY KEY (contract, proposal_id, ts);
+
+ALTER TABLE rfps DROP CONSTRAINT rfps_pkey,
+ADD PRIMARY KEY (contract, id);
+
+ALTER TABLE rfp_snapshots DROP CONSTRAINT rfp_snapshots_pkey,
+ADD PRIMARY KEY (contract, rfp_id, ts),
+ADD FOREIGN KEY (contract, rfp_id) REFERENCES rfps (contract, id);
+
+ALTER TABLE communities DROP CONSTRAINT communities_pkey,
+ADD PRIMARY KEY (contract, handle);
+
+ALTER TABLE community_snapshots DROP CONSTRAINT community_snapshots_pkey,
+ADD PRIMARY KEY (contract, handle, ts),
+ADD FOREIGN KEY (contract, handle) REFERENCES communities (contract, handle);
+
+ALTER TABLE addons DROP CONSTRAINT addons_pkey,
+ADD PRIMARY KEY (contract, id);
+
+ALTER TABLE addon_snapshots DROP CONSTRAINT addon_snapshots_pkey,
+ADD PRIMARY KEY (contract, addon_id, ts),
+ADD FOREIGN KEY (contract, addon_id) REFERENCES addons (contract, id);
+
+CREATE INDEX idx_dumps_contract_proposal_id ON dumps (contract, proposal_id);
+
+CREATE INDEX idx_rfp_dumps_contract_rfp_id ON rfp_dumps (contract, rfp_id);
+
+CREATE INDEX idx_community_dumps_contract ON community_dumps (contract);
+
+-- The views join on the contract as well
+DROP VIEW proposals_with_latest_snapshot;
+
+CREATE VIEW
+  proposals_with_latest_snapshot AS
+SELECT
+  ps.contract,
+  ps.proposal_id,
+  p.author_id,
+  ps.block_height,
+  ps.ts,
+  ps.editor_id,
+  ps.social_db_post_block_height,
+  ps.labels,
+  ps.proposal_version,
+  ps.proposal_body_version,
+  ps.name,
+  ps.category,
+  ps.summary,
+  ps.description,
+  ps.linked_proposals,
+  ps.linked_rfp,
+  ps.requested_sponsorship_usd_amount,
+  ps.requested_sponsorship_paid_in_currency,
+  ps.requested_sponsor,
+  ps.receiver_account,
+  ps.supervisor,
+  ps.timeline,
+  ps.timeline_status,
+  ps.views
+FROM
+  proposals p
+  INNER JOIN (
+    SELECT
+      contract,
+      proposal_id,
+      MAX(ts) AS max_ts
+    FROM
+      proposal_snapshots
+    GROUP BY
+      contract,
+      proposal_id
+  ) latest_snapshots ON p.contract = latest_snapshots.contract
+  AND p.id = latest_snapshots.proposal_id
+  INNER JOIN proposal_snapshots ps ON latest_snapshots.contract = ps.contract
+  AND latest_snapshots.proposal_id = ps.proposal_id
+  AND latest_snapshots.max_ts = ps.ts;
+
+DROP VIEW rfps_with_latest_snapshot;
+
+CREATE VIEW
+  rfps_with_latest_snapshot AS
+SELECT
+  ps.contract,
+  ps.rfp_id,
+  p.author_id,
+  ps.block_height,
+  ps.ts,
+  ps.editor_id,
+  ps.social_db_post_block_height,
+  ps.labels,
+  ps.linked_proposals,
+  ps.rfp_version,
+  ps.rfp_body_version,
+  ps.name,
+  ps.category,
+  ps.summary,
+  ps.description,
+  ps.timeline,
+  ps.views,
+  ps.submission_deadline
+FROM
+  rfps p
+  INNER JOIN (
+    SELECT
+      contract,
+      rfp_id,
+      MAX(ts) AS max_ts
+    FROM
+      rfp_snapshots
+    GROUP BY
+      contract,
+      rfp_id
+  ) latest_snapshots ON p.contract = latest_snapshots.contract
+  AND p.id = latest_snapshots.rfp_id
+  INNER JOIN rfp_snapshots ps ON latest_snapshots.contract = ps.contract
+  AND latest_snapshots.rfp_id = ps.rfp_id
+  AND latest_snapshots.max_ts = ps.ts;
+
+DROP VIEW communities_with_latest_snapshot;
+
+CREATE VIEW
+  communities_with_latest_snapshot AS
+SELECT
+  cs.*
+FROM
+  community_snapshots cs
+  INNER JOIN (
+    SELECT
+      contract,
+      handle,
+      MAX(ts) AS max_ts
+    FROM
+      community_snapshots
+    GROUP BY
+      contract,
+      handle
+  ) latest_snapshots ON cs.contract = latest_snapshots.contract
+  AND cs.handle = latest_snapshots.handle
+  AND cs.ts = latest_snapshots.max_ts;
+
+DROP VIEW addons_with_latest_snapshot;
+
+CREATE VIEW
+  addons_with_latest_snapshot AS
+SELECT
+  ads.*
+FROM
+  addon_snapshots ads
+  INNER JOIN (
+    SELECT
+      contract,
+      addon_id,
+      MAX(ts) AS max_ts
+    FROM
+      addon_snapshots
+    GROUP BY
+      contract,
+      addon_id
+  ) latest_snapshots ON ads.contract = latest_snapshots.contract
+  AND ads.addon_id = latest_snapshots.addon_id
+  AND ads.ts = latest_snapshots.max_ts;
@@ -0,0 +1,4 @@
+-- Id of the last processed transaction in nearblocks, passed back as the `cursor` of the next
+-- request so a sync resumes right after it instead of at the start of its day
+ALTER TABLE sync_cursors
+ADD COLUMN nearblocks_cursor varchar;
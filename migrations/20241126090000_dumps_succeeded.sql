@@ -0,0 +1,7 @@
+-- Failed receipts and the `add_proposal`/`add_rfp` calls are stored too, so the dumps hold
+-- every receipt of the contract. Replays skip the failed ones.
+ALTER TABLE dumps
+ADD COLUMN succeeded boolean NOT NULL DEFAULT true;
+
+ALTER TABLE rfp_dumps
+ADD COLUMN succeeded boolean NOT NULL DEFAULT true;
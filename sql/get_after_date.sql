@@ -1 +0,0 @@
-SELECT after_date FROM after_date;
\ No newline at end of file
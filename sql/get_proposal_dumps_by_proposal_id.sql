@@ -1,6 +0,0 @@
-SELECT
-    d.*
-FROM
-    dumps d
-WHERE
-    d.proposal_id = $1;
\ No newline at end of file
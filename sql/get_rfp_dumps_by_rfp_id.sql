@@ -1,6 +0,0 @@
-SELECT
-    rd.*
-FROM
-    rfp_dumps rd
-WHERE
-    rd.rfp_id = $1;
\ No newline at end of file
@@ -1,38 +1,59 @@
-use crate::api_client::ApiClient;
+use crate::db::{types::SyncCursorRecord, DB};
+use crate::error::Error;
+use crate::nearblocks_client::types::{MalformedTransaction, Transaction};
+use crate::sources::TransactionSource;
+use near_account_id::AccountId;
+use std::future::Future;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
 use tokio::{
-    signal,
     task::JoinHandle,
-    time::{self, Duration},
+    time::{self, Duration, MissedTickBehavior},
 };
 
+// Method family of the quarantined transactions that nearblocks returned in a shape we
+// couldn't decode, see `fetch_unprocessed_txns`
+pub const UNDECODED_METHOD_FAMILY: &str = "undecoded";
+
 pub struct ApiBackgroundService {
-    api_client: ApiClient,
+    db: DB,
+    interval: Duration,
     handle: Option<JoinHandle<()>>,
 }
 
 impl ApiBackgroundService {
-    pub fn new(api_client: ApiClient) -> Self {
+    pub fn new(db: DB, interval: Duration) -> Self {
         Self {
-            api_client,
+            db,
+            interval,
             handle: None,
         }
     }
 
-    // Spawns the background task and starts it
-    pub fn start(&mut self) {
-        let api_client = self.api_client.clone();
+    // Spawns the background task and runs `sync` on every tick until `running` is cleared
+    pub fn start<F, Fut>(&mut self, running: Arc<AtomicBool>, sync: F)
+    where
+        F: Fn(DB) -> Fut + Send + 'static,
+        Fut: Future<Output = anyhow::Result<()>> + Send,
+    {
+        let db = self.db.clone();
+        let period = self.interval;
         let handle = tokio::spawn(async move {
-            let mut interval = time::interval(Duration::from_secs(60));
+            let mut interval = time::interval(period);
+            // A slow nearblocks response should delay the next sync, not trigger a burst of them
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
             loop {
                 interval.tick().await;
 
-                match api_client.get_data().await {
-                    Ok(response) => {
-                        println!("Received data: {:?}", response.data);
-                    }
-                    Err(e) => {
-                        eprintln!("Error fetching data: {:?}", e);
-                    }
+                if !running.load(Ordering::Relaxed) {
+                    println!("Background indexer stopped");
+                    break;
+                }
+
+                if let Err(e) = sync(db.clone()).await {
+                    eprintln!("Error syncing from nearblocks: {:?}", e);
                 }
             }
         });
@@ -47,10 +68,205 @@ impl ApiBackgroundService {
             println!("Background task aborted");
         }
     }
+}
 
-    // Wait for a shutdown signal (e.g., Ctrl+C)
-    pub async fn wait_for_shutdown(self) {
-        signal::ctrl_c().await.expect("Failed to listen for Ctrl+C");
-        self.shutdown().await;
+// Fetches every transaction of `contract` that one of `method_families` hasn't processed
+// yet, oldest block first. The families share this one fetch, each of them skips what it
+// already processed with `unprocessed_txns`.
+pub async fn fetch_unprocessed_txns(
+    db: &DB,
+    source: &dyn TransactionSource,
+    contract: &str,
+    method_families: &[&str],
+) -> anyhow::Result<Vec<Transaction>> {
+    let mut cursors = vec![];
+    for method_family in method_families {
+        cursors.push(db.get_sync_cursor(contract, method_family).await?);
     }
+    // Starts at the family that is furthest behind, or at the beginning if one never ran
+    let oldest = cursors
+        .into_iter()
+        .collect::<Option<Vec<_>>>()
+        .and_then(|cursors| cursors.into_iter().min_by_key(|cursor| cursor.block_height));
+
+    println!("Fetching not yet indexed method calls of {}", contract);
+
+    let items = source
+        .account_txns(
+            &contract.parse::<AccountId>()?,
+            None,
+            oldest.as_ref().map(|cursor| cursor.block_timestamp),
+            oldest
+                .as_ref()
+                .and_then(|cursor| cursor.nearblocks_cursor.clone()),
+        )
+        .await?;
+
+    let mut transactions = vec![];
+    for item in items {
+        match item {
+            Ok(transaction) => transactions.push(transaction),
+            // Older ones were skipped by every family already
+            Err(malformed)
+                if oldest.as_ref().is_some_and(|cursor| {
+                    malformed.block_height().unwrap_or_default() < cursor.block_height
+                }) => {}
+            Err(malformed) => record_malformed_transaction(db, contract, &malformed).await?,
+        }
+    }
+
+    println!(
+        "Fetched {} method calls of {}",
+        transactions.len(),
+        contract
+    );
+
+    Ok(transactions)
+}
+
+// The transactions after the sync cursor of `method_family`. Sources may start before the
+// cursor, e.g. nearblocks returns the whole day of it, so we skip everything up to and
+// including the cursor here.
+pub async fn unprocessed_txns(
+    db: &DB,
+    contract: &str,
+    method_family: &str,
+    transactions: &[Transaction],
+) -> anyhow::Result<Vec<Transaction>> {
+    // Get the last block and receipt we processed
+    let cursor = db.get_sync_cursor(contract, method_family).await?;
+
+    Ok(after_cursor(cursor.as_ref(), transactions).to_vec())
+}
+
+// The transactions are ordered, so everything up to the receipt of the cursor was processed,
+// including the receipts of the same block before it
+fn after_cursor<'a>(
+    cursor: Option<&SyncCursorRecord>,
+    transactions: &'a [Transaction],
+) -> &'a [Transaction] {
+    let Some(cursor) = cursor else {
+        return transactions;
+    };
+
+    let start = match transactions
+        .iter()
+        .position(|transaction| cursor.receipt_id.as_ref() == Some(&transaction.receipt_id))
+    {
+        Some(position) => position + 1,
+        // Without its receipt only the blocks before the cursor are known to be processed,
+        // the receipts of its block are processed again
+        None => transactions
+            .iter()
+            .position(|transaction| transaction.block.block_height >= cursor.block_height)
+            .unwrap_or(transactions.len()),
+    };
+
+    &transactions[start..]
+}
+
+// Called after every processed receipt so a restart resumes right after it
+pub async fn advance_sync_cursor(
+    db: &DB,
+    contract: &str,
+    method_family: &str,
+    transaction: &Transaction,
+) -> anyhow::Result<()> {
+    db.set_sync_cursor(&SyncCursorRecord {
+        contract: contract.to_string(),
+        method_family: method_family.to_string(),
+        block_height: transaction.block.block_height,
+        block_timestamp: transaction.block_timestamp.parse()?,
+        receipt_id: Some(transaction.receipt_id.clone()),
+        nearblocks_cursor: Some(transaction.id.clone()),
+    })
+    .await?;
+    Ok(())
+}
+
+// Processes the transactions in order and advances the sync cursor after each one.
+// A receipt that fails for good is recorded in `failed_receipts` and skipped, so one
+// malformed payload doesn't block the indexer. Any other error stops the sync and the
+// receipt is tried again on the next run.
+pub async fn process_and_advance<F, Fut>(
+    db: &DB,
+    contract: &str,
+    method_family: &str,
+    transactions: Vec<Transaction>,
+    process: F,
+) -> anyhow::Result<()>
+where
+    F: Fn(Transaction) -> Fut,
+    Fut: Future<Output = Result<(), Error>>,
+{
+    for transaction in transactions {
+        match process(transaction.clone()).await {
+            Ok(()) => {}
+            Err(e) if e.is_permanent() => {
+                eprintln!(
+                    "Skipping receipt {} of {}: {}",
+                    transaction.receipt_id, method_family, e
+                );
+                record_failed_receipt(db, contract, method_family, &transaction, &e).await?;
+            }
+            Err(e) => return Err(e.into()),
+        }
+
+        advance_sync_cursor(db, contract, method_family, &transaction).await?;
+    }
+
+    Ok(())
+}
+
+// Quarantines a transaction nearblocks returned that we couldn't decode. It isn't known
+// which method family it belongs to, so a retry hands it to all of them.
+async fn record_malformed_transaction(
+    db: &DB,
+    contract: &str,
+    malformed: &MalformedTransaction,
+) -> anyhow::Result<()> {
+    let (Some(receipt_id), Some(block_height)) = (malformed.receipt_id(), malformed.block_height())
+    else {
+        eprintln!(
+            "Skipping malformed transaction of {} without a receipt: {}",
+            contract, malformed.error
+        );
+        return Ok(());
+    };
+
+    eprintln!("Skipping receipt {}: {}", receipt_id, malformed.error);
+    db.upsert_failed_receipt(
+        receipt_id,
+        contract,
+        UNDECODED_METHOD_FAMILY,
+        malformed.method(),
+        block_height,
+        malformed.block_timestamp().unwrap_or_default(),
+        &malformed.raw,
+        &malformed.error,
+    )
+    .await
+}
+
+pub async fn record_failed_receipt(
+    db: &DB,
+    contract: &str,
+    method_family: &str,
+    transaction: &Transaction,
+    error: &Error,
+) -> anyhow::Result<()> {
+    db.upsert_failed_receipt(
+        &transaction.receipt_id,
+        contract,
+        method_family,
+        transaction
+            .actions
+            .first()
+            .map(|action| action.method.as_str()),
+        transaction.block.block_height,
+        transaction.block_timestamp.parse().unwrap_or_default(),
+        &serde_json::to_value(transaction)?,
+        &error.to_string(),
+    )
+    .await
 }
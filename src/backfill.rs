@@ -0,0 +1,135 @@
+use devhub_cache_api::config::Config;
+use devhub_cache_api::db::types::{ProposalSnapshotRecord, RfpSnapshotRecord};
+use devhub_cache_api::db::DB;
+use devhub_cache_api::entrypoints::proposal::types::FromContractProposal;
+use devhub_cache_api::entrypoints::rfp::types::FromContractRfp;
+use devhub_cache_api::sources::{at_block_height, ContractReader, Sources};
+use devhub_shared::proposal::Proposal;
+use devhub_shared::rfp::RFP;
+use near_account_id::AccountId;
+use std::collections::HashSet;
+
+// Ids read from the contract per RPC call
+const BACKFILL_BATCH: usize = 50;
+
+// Seeds the cache with the current state of every proposal and RFP of the configured contracts.
+// Ids that are already cached are skipped, the indexer keeps those up to date, so an
+// interrupted backfill picks up where it stopped when it is run again.
+pub async fn run(db: &DB, config: &Config, sources: &Sources) -> anyhow::Result<()> {
+    for contract in config.contracts.iter() {
+        let contract_reader = sources.contract_reader(contract);
+        backfill_proposals(db, contract_reader.as_ref(), contract).await?;
+        backfill_rfps(db, contract_reader.as_ref(), contract).await?;
+    }
+
+    Ok(())
+}
+
+async fn backfill_proposals(
+    db: &DB,
+    contract_reader: &dyn ContractReader,
+    contract: &AccountId,
+) -> anyhow::Result<()> {
+    let contract = contract.as_str();
+    let all_ids = contract_reader.get_all_proposal_ids().await?;
+    // Every batch is read at the block of the id list, so they all see the same state
+    let block = at_block_height(all_ids.block_height as i64);
+    let cached: HashSet<i32> = db.get_proposal_ids(contract).await?.into_iter().collect();
+    let ids: Vec<i32> = all_ids
+        .data
+        .into_iter()
+        .filter(|id| !cached.contains(id))
+        .collect();
+
+    println!(
+        "Backfilling {} proposals of {} at block {}, {} already cached",
+        ids.len(),
+        contract,
+        all_ids.block_height,
+        cached.len()
+    );
+
+    let mut done = 0;
+    for batch in ids.chunks(BACKFILL_BATCH) {
+        let proposals = contract_reader.get_proposals(batch, block.clone()).await?;
+
+        let mut tx = db.begin().await?;
+        for versioned_proposal in proposals.data {
+            let proposal: Proposal = versioned_proposal.into();
+            DB::upsert_proposal(
+                &mut tx,
+                contract,
+                proposal.id,
+                proposal.author_id.to_string(),
+            )
+            .await?;
+
+            let snapshot = ProposalSnapshotRecord::from_contract_proposal(
+                contract,
+                proposal.clone(),
+                proposal.snapshot.timestamp.to_string(),
+                proposals.block_height as i64,
+            )?;
+            DB::insert_proposal_snapshot(&mut tx, &snapshot).await?;
+        }
+        tx.commit().await?;
+
+        done += batch.len();
+        println!(
+            "Backfilled {}/{} proposals of {}",
+            done,
+            ids.len(),
+            contract
+        );
+    }
+
+    Ok(())
+}
+
+async fn backfill_rfps(
+    db: &DB,
+    contract_reader: &dyn ContractReader,
+    contract: &AccountId,
+) -> anyhow::Result<()> {
+    let contract = contract.as_str();
+    let all_ids = contract_reader.get_all_rfp_ids().await?;
+    let block = at_block_height(all_ids.block_height as i64);
+    let cached: HashSet<i32> = db.get_rfp_ids(contract).await?.into_iter().collect();
+    let ids: Vec<i32> = all_ids
+        .data
+        .into_iter()
+        .filter(|id| !cached.contains(id))
+        .collect();
+
+    println!(
+        "Backfilling {} rfps of {} at block {}, {} already cached",
+        ids.len(),
+        contract,
+        all_ids.block_height,
+        cached.len()
+    );
+
+    let mut done = 0;
+    for batch in ids.chunks(BACKFILL_BATCH) {
+        let rfps = contract_reader.get_rfps(batch, block.clone()).await?;
+
+        let mut tx = db.begin().await?;
+        for versioned_rfp in rfps.data {
+            let rfp: RFP = versioned_rfp.into();
+            DB::upsert_rfp(&mut tx, contract, rfp.id, rfp.author_id.to_string()).await?;
+
+            let snapshot = RfpSnapshotRecord::from_contract_rfp(
+                contract,
+                rfp.clone(),
+                rfp.snapshot.timestamp.to_string(),
+            )?;
+            DB::upsert_rfp_snapshot(&mut tx, &snapshot).await?;
+        }
+        tx.commit().await?;
+
+        done += batch.len();
+        println!("Backfilled {}/{} rfps of {}", done, ids.len(), contract);
+    }
+
+    Ok(())
+}
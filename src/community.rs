@@ -0,0 +1,47 @@
+// Mirrors the community types of the devhub contract, devhub-shared only covers proposals and RFPs.
+// Fields that only older communities use (wiki pages, boards, feature flags) are left out,
+// serde skips them when decoding.
+use near_account_id::AccountId;
+use serde::{Deserialize, Serialize};
+
+pub type CommunityHandle = String;
+pub type AddOnId = String;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Community {
+    pub admins: Vec<AccountId>,
+    pub handle: CommunityHandle,
+    pub name: String,
+    pub tag: String,
+    pub description: String,
+    pub logo_url: String,
+    pub banner_url: String,
+    pub bio_markdown: Option<String>,
+    pub github_handle: Option<String>,
+    pub telegram_handle: Option<String>,
+    pub twitter_handle: Option<String>,
+    pub website_url: Option<String>,
+    #[serde(default)]
+    pub addons: Vec<CommunityAddOn>,
+}
+
+// An addon as configured on a single community
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CommunityAddOn {
+    pub id: String,
+    pub addon_id: AddOnId,
+    pub display_name: String,
+    pub enabled: bool,
+    pub parameters: String,
+}
+
+// An addon that communities can enable
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AddOn {
+    pub id: AddOnId,
+    pub title: String,
+    pub description: String,
+    pub icon: String,
+    pub view_widget: String,
+    pub configurator_widget: String,
+}
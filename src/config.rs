@@ -0,0 +1,124 @@
+use crate::error::{Error, Result};
+use crate::nearblocks_client::ApiClient;
+use near_account_id::AccountId;
+use near_api::NetworkConfig;
+use rocket::fairing::AdHoc;
+use rocket::serde::Deserialize;
+use std::path::PathBuf;
+use url::Url;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(crate = "rocket::serde", rename_all = "lowercase")]
+pub enum Network {
+    #[default]
+    Mainnet,
+    Testnet,
+}
+
+// Read from `Rocket.toml` and `ROCKET_*` env vars, e.g.
+//
+// [default]
+// contracts = ["devhub.near", "events-committee.near"]
+// network = "mainnet"
+//
+// [testnet]
+// contracts = ["devhub.testnet"]
+// network = "testnet"
+// rpc_url = "https://rpc.testnet.near.org"
+#[derive(Debug, Clone, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Config {
+    // Accounts of the DevHub contract and its forks to index, the first one also
+    // serves the routes that aren't scoped by a contract
+    #[serde(default = "default_contracts")]
+    pub contracts: Vec<AccountId>,
+    #[serde(default)]
+    pub network: Network,
+    // Overrides the archival RPC of `network`
+    #[serde(default)]
+    pub rpc_url: Option<Url>,
+    // Overrides the nearblocks API of `network`
+    #[serde(default)]
+    pub nearblocks_api_url: Option<Url>,
+    // Admin endpoints are disabled when unset
+    #[serde(default)]
+    pub admin_api_key: Option<String>,
+    // Recorded nearblocks and RPC responses to read instead of the network, see `Fixtures`
+    #[serde(default)]
+    pub fixtures: Option<PathBuf>,
+}
+
+fn default_contracts() -> Vec<AccountId> {
+    vec!["devhub.near".parse().unwrap()]
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            contracts: default_contracts(),
+            network: Network::default(),
+            rpc_url: None,
+            nearblocks_api_url: None,
+            admin_api_key: None,
+            fixtures: None,
+        }
+    }
+}
+
+impl Config {
+    pub fn network_config(&self) -> NetworkConfig {
+        let mut network = match self.network {
+            Network::Mainnet => NetworkConfig::mainnet(),
+            Network::Testnet => NetworkConfig::testnet(),
+        };
+        if let Some(rpc_url) = &self.rpc_url {
+            network.rpc_url = rpc_url.clone();
+        }
+        network
+    }
+
+    pub fn nearblocks_api_url(&self) -> String {
+        match (&self.nearblocks_api_url, self.network) {
+            (Some(url), _) => url.to_string(),
+            (None, Network::Mainnet) => "https://api.nearblocks.io/".to_string(),
+            (None, Network::Testnet) => "https://api-testnet.nearblocks.io/".to_string(),
+        }
+    }
+
+    // The contract that serves requests without a contract in the path
+    pub fn default_contract(&self) -> &AccountId {
+        &self.contracts[0]
+    }
+
+    // Looks up a contract from a request path, only configured contracts are served
+    pub fn contract(&self, contract: &str) -> Result<&AccountId> {
+        self.contracts
+            .iter()
+            .find(|account_id| account_id.as_str() == contract)
+            .ok_or_else(|| Error::NotFound(format!("Contract {} is not indexed", contract)))
+    }
+
+    pub fn nearblocks_client(&self) -> ApiClient {
+        ApiClient::with_base_url(&self.nearblocks_api_url())
+    }
+}
+
+// Extracts `Config` from the figment and puts it in managed state
+pub fn stage() -> AdHoc {
+    AdHoc::try_on_ignite("Config", |rocket| async {
+        let config = match rocket.figment().extract::<Config>() {
+            Ok(config) => config,
+            Err(e) => {
+                rocket::config::pretty_print_error(e);
+                return Err(rocket);
+            }
+        };
+
+        if config.contracts.is_empty() {
+            rocket::error!("`contracts` needs at least one contract to index");
+            return Err(rocket);
+        }
+
+        Ok(rocket.manage(config))
+    })
+}
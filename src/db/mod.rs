@@ -3,7 +3,7 @@ use rocket::{
     Build, Rocket,
 };
 use rocket_db_pools::Database;
-use sqlx::{migrate, query, query_scalar, Error, PgPool, Postgres, Transaction};
+use sqlx::{migrate, query, Error, PgPool, Postgres, Transaction};
 
 #[derive(Database, Clone, Debug)]
 #[database("devhub_cache_api_rs")]
@@ -11,21 +11,28 @@ pub struct DB(PgPool);
 
 pub mod types;
 
-use types::{ProposalRecord, ProposalSnapshotRecord, ProposalWithLatestSnapshotView};
+use types::{
+    AddonSnapshotRecord, AuthorEditRecord, AuthorSummary, CategorySummary, CommunityAddonView,
+    CommunityDumpRecord, CommunitySnapshotRecord, DumpRecord, FailedReceiptRecord, LabelCount,
+    ProposalRecord, ProposalSearchResult, ProposalSnapshotRecord, ProposalWithLatestSnapshotView,
+    RfpDumpRecord, RfpSnapshotRecord, RfpWithLatestSnapshotView, SyncCursorRecord,
+};
 
 impl DB {
     // Functions for Proposals
     pub async fn upsert_proposal(
         tx: &mut Transaction<'static, Postgres>,
+        contract: &str,
         proposal_id: u32,
         author_id: String,
     ) -> Result<i32, Error> {
         let rec = sqlx::query!(
             r#"
-            UPDATE proposals SET author_id = $1 WHERE id = $2
+            UPDATE proposals SET author_id = $1 WHERE contract = $2 AND id = $3
             RETURNING id
             "#,
             author_id,
+            contract,
             proposal_id as i32
         )
         .fetch_optional(tx.as_mut())
@@ -38,11 +45,12 @@ impl DB {
             // INSERT ON CONFLICT DO NOTHING
             let rec = sqlx::query!(
                 r#"
-                INSERT INTO proposals (id, author_id)
-                VALUES ($1, $2)
-                ON CONFLICT (id) DO NOTHING
+                INSERT INTO proposals (contract, id, author_id)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (contract, id) DO NOTHING
                 RETURNING id
                 "#,
+                contract,
                 proposal_id as i32,
                 author_id
             )
@@ -52,48 +60,150 @@ impl DB {
         }
     }
 
-    // TODO db.get_last_updated_timestamp
-    pub async fn get_last_updated_timestamp(&self) -> Result<i64, Error> {
-        // let rec = sqlx::query_file_as!(i64, "./sql/get_after_date.sql")
-        //     .fetch_one(&self)
-        //     .await?;
-
-        let rec = query_scalar!(
+    pub async fn get_sync_cursor(
+        &self,
+        contract: &str,
+        method_family: &str,
+    ) -> Result<Option<SyncCursorRecord>, Error> {
+        let rec = sqlx::query_as!(
+            SyncCursorRecord,
             r#"
-            SELECT after_date FROM after_date
-            "#
+            SELECT contract, method_family, block_height, block_timestamp, receipt_id, nearblocks_cursor
+            FROM sync_cursors
+            WHERE contract = $1 AND method_family = $2
+            "#,
+            contract,
+            method_family
         )
-        .fetch_one(&self.0)
+        .fetch_optional(&self.0)
         .await?;
         Ok(rec)
     }
 
-    pub async fn set_last_updated_timestamp(&self, after_date: i64) -> Result<(), Error> {
+    pub async fn set_sync_cursor(&self, cursor: &SyncCursorRecord) -> Result<(), Error> {
         sqlx::query!(
             r#"
-            UPDATE after_date SET after_date = $1
+            INSERT INTO sync_cursors (contract, method_family, block_height, block_timestamp, receipt_id, nearblocks_cursor)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (contract, method_family) DO UPDATE SET
+                block_height = $3,
+                block_timestamp = $4,
+                receipt_id = $5,
+                nearblocks_cursor = $6
             "#,
-            after_date
+            cursor.contract,
+            cursor.method_family,
+            cursor.block_height,
+            cursor.block_timestamp,
+            cursor.receipt_id,
+            cursor.nearblocks_cursor
         )
         .execute(&self.0)
         .await?;
         Ok(())
     }
+
     // TODO db.get_proposals
     pub async fn get_proposals(&self) -> Vec<ProposalRecord> {
         vec![]
     }
 
+    // Ids of the proposals of `contract` that have a snapshot in the cache
+    pub async fn get_proposal_ids(&self, contract: &str) -> anyhow::Result<Vec<i32>> {
+        let ids = sqlx::query_scalar::<_, i32>(
+            "SELECT DISTINCT proposal_id FROM proposal_snapshots WHERE contract = $1",
+        )
+        .bind(contract)
+        .fetch_all(&self.0)
+        .await?;
+
+        Ok(ids)
+    }
+
+    // Every stored proposal receipt of `contract` by block. Nearblocks doesn't tell in which
+    // order the receipts of one block ran, so those are only ordered by `receipt_id` to
+    // keep replays deterministic. Edits of one proposal in the same block share a snapshot
+    // anyway, the one replayed last wins.
+    pub async fn get_all_dumps(&self, contract: &str) -> anyhow::Result<Vec<DumpRecord>> {
+        let recs = sqlx::query_as::<_, DumpRecord>(
+            r#"
+            SELECT
+                *
+            FROM
+                dumps
+            WHERE
+                contract = $1
+            ORDER BY
+                block_height,
+                block_timestamp,
+                receipt_id
+            "#,
+        )
+        .bind(contract)
+        .fetch_all(&self.0)
+        .await?;
+
+        Ok(recs)
+    }
+
+    pub async fn delete_proposal_snapshots(
+        tx: &mut Transaction<'static, Postgres>,
+        contract: &str,
+    ) -> anyhow::Result<u64> {
+        let result = sqlx::query("DELETE FROM proposal_snapshots WHERE contract = $1")
+            .bind(contract)
+            .execute(tx.as_mut())
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    // Replaces the proposal snapshots of `contract` with the ones staged under `staging`
+    pub async fn swap_proposal_snapshots(
+        tx: &mut Transaction<'static, Postgres>,
+        staging: &str,
+        contract: &str,
+    ) -> anyhow::Result<u64> {
+        Self::delete_proposal_snapshots(tx, contract).await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO proposals (contract, id, author_id)
+            SELECT $2, id, author_id FROM proposals WHERE contract = $1
+            ON CONFLICT (contract, id) DO UPDATE SET author_id = EXCLUDED.author_id
+            "#,
+        )
+        .bind(staging)
+        .bind(contract)
+        .execute(tx.as_mut())
+        .await?;
+
+        let result = sqlx::query("UPDATE proposal_snapshots SET contract = $2 WHERE contract = $1")
+            .bind(staging)
+            .bind(contract)
+            .execute(tx.as_mut())
+            .await?;
+
+        sqlx::query("DELETE FROM proposals WHERE contract = $1")
+            .bind(staging)
+            .execute(tx.as_mut())
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
     pub async fn get_proposal_by_id(
         tx: &mut Transaction<'static, Postgres>,
+        contract: &str,
         proposal_id: i32,
     ) -> anyhow::Result<Option<ProposalRecord>> {
         let rec = query!(
             r#"
           SELECT id, author_id
           FROM proposals
-          WHERE id = $1
+          WHERE contract = $1 AND id = $2
           "#,
+            contract,
             proposal_id
         )
         .fetch_optional(tx.as_mut())
@@ -116,7 +226,7 @@ impl DB {
         tx: &mut Transaction<'static, Postgres>,
         snapshot: &ProposalSnapshotRecord,
     ) -> anyhow::Result<()> {
-        // Since primary key is (proposal_id, ts)
+        // Since primary key is (contract, proposal_id, ts)
         query!(
             r#"
           INSERT INTO proposal_snapshots (
@@ -140,12 +250,13 @@ impl DB {
               receiver_account,
               supervisor,
               timeline,
-              views
+              views,
+              contract
           ) VALUES (
               $1, $2, $3, $4, $5, $6, $7, $8,
               $9, $10, $11, $12, $13, $14,
-              $15, $16, $17, $18, $19, $20, $21
-          ) ON CONFLICT (proposal_id, ts) DO UPDATE SET
+              $15, $16, $17, $18, $19, $20, $21, $22
+          ) ON CONFLICT (contract, proposal_id, ts) DO UPDATE SET
               block_height = $2,
               editor_id = $4,
               social_db_post_block_height = $5,
@@ -186,7 +297,173 @@ impl DB {
             snapshot.receiver_account,
             snapshot.supervisor,
             snapshot.timeline,
-            snapshot.views
+            snapshot.views,
+            snapshot.contract
+        )
+        .execute(tx.as_mut())
+        .await?;
+        Ok(())
+    }
+
+    pub async fn get_proposal_with_latest_snapshot(
+        &self,
+        contract: &str,
+        proposal_id: i32,
+    ) -> anyhow::Result<Option<ProposalWithLatestSnapshotView>> {
+        let rec = sqlx::query_as::<_, ProposalWithLatestSnapshotView>(
+            r#"
+            SELECT
+                *
+            FROM
+                proposals_with_latest_snapshot
+            WHERE
+                contract = $1
+                AND proposal_id = $2
+            "#,
+        )
+        .bind(contract)
+        .bind(proposal_id)
+        .fetch_optional(&self.0)
+        .await?;
+
+        Ok(rec)
+    }
+
+    // Latest snapshot of a proposal at or before `ts`, or the latest one overall without `ts`
+    pub async fn get_latest_proposal_snapshot(
+        &self,
+        contract: &str,
+        proposal_id: i32,
+        ts: Option<i64>,
+    ) -> anyhow::Result<Option<ProposalSnapshotRecord>> {
+        let rec = sqlx::query_as::<_, ProposalSnapshotRecord>(
+            r#"
+            SELECT
+                *
+            FROM
+                proposal_snapshots ps
+            WHERE
+                ps.contract = $3
+                AND ps.proposal_id = $1
+                AND ($2::bigint IS NULL OR ps.ts <= $2)
+            ORDER BY
+                ps.ts DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(proposal_id)
+        .bind(ts)
+        .bind(contract)
+        .fetch_optional(&self.0)
+        .await?;
+
+        Ok(rec)
+    }
+
+    // Returns one page of the snapshot history together with the total number of matching snapshots
+    #[allow(clippy::too_many_arguments)]
+    pub async fn get_proposal_snapshots(
+        &self,
+        contract: &str,
+        proposal_id: i32,
+        limit: i64,
+        order: &str,
+        offset: i64,
+        from_ts: Option<i64>,
+        to_ts: Option<i64>,
+        editor_id: Option<String>,
+    ) -> anyhow::Result<(Vec<ProposalSnapshotRecord>, i64)> {
+        // Validate the order clause to prevent SQL injection
+        let order_clause = match order.to_lowercase().as_str() {
+            "asc" => "ASC",
+            "desc" => "DESC",
+            _ => "ASC", // History reads oldest first by default
+        };
+
+        let sql = format!(
+            r#"
+            SELECT
+                *
+            FROM
+                proposal_snapshots ps
+            WHERE
+                ps.proposal_id = $3
+                AND ($4::bigint IS NULL OR ps.ts >= $4)
+                AND ($5::bigint IS NULL OR ps.ts <= $5)
+                AND ($6::varchar IS NULL OR ps.editor_id = $6)
+                AND ps.contract = $7
+            ORDER BY ps.ts {}
+            LIMIT $1 OFFSET $2
+            "#,
+            order_clause,
+        );
+
+        let recs = sqlx::query_as::<_, ProposalSnapshotRecord>(&sql)
+            .bind(limit)
+            .bind(offset)
+            .bind(proposal_id)
+            .bind(from_ts)
+            .bind(to_ts)
+            .bind(editor_id.clone())
+            .bind(contract)
+            .fetch_all(&self.0)
+            .await?;
+
+        let total = sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT
+                COUNT(*)
+            FROM
+                proposal_snapshots ps
+            WHERE
+                ps.proposal_id = $1
+                AND ($2::bigint IS NULL OR ps.ts >= $2)
+                AND ($3::bigint IS NULL OR ps.ts <= $3)
+                AND ($4::varchar IS NULL OR ps.editor_id = $4)
+                AND ps.contract = $5
+            "#,
+        )
+        .bind(proposal_id)
+        .bind(from_ts)
+        .bind(to_ts)
+        .bind(editor_id)
+        .bind(contract)
+        .fetch_one(&self.0)
+        .await?;
+
+        Ok((recs, total))
+    }
+
+    // Primary key is receipt_id, so recording the same receipt twice is a no-op
+    pub async fn insert_dump(
+        tx: &mut Transaction<'static, Postgres>,
+        dump: &DumpRecord,
+    ) -> anyhow::Result<()> {
+        query!(
+            r#"
+          INSERT INTO dumps (
+              receipt_id,
+              method_name,
+              block_height,
+              block_timestamp,
+              args,
+              author,
+              proposal_id,
+              contract,
+              succeeded
+          ) VALUES (
+              $1, $2, $3, $4, $5, $6, $7, $8, $9
+          ) ON CONFLICT (receipt_id) DO NOTHING
+          "#,
+            dump.receipt_id,
+            dump.method_name,
+            dump.block_height,
+            dump.block_timestamp,
+            dump.args,
+            dump.author,
+            dump.proposal_id,
+            dump.contract,
+            dump.succeeded
         )
         .execute(tx.as_mut())
         .await?;
@@ -215,17 +492,103 @@ impl DB {
 
     // Functions for RFPs
 
+    // Ids of the RFPs of `contract` that have a snapshot in the cache
+    pub async fn get_rfp_ids(&self, contract: &str) -> anyhow::Result<Vec<i32>> {
+        let ids = sqlx::query_scalar::<_, i32>(
+            "SELECT DISTINCT rfp_id FROM rfp_snapshots WHERE contract = $1",
+        )
+        .bind(contract)
+        .fetch_all(&self.0)
+        .await?;
+
+        Ok(ids)
+    }
+
+    // Every stored RFP receipt of `contract` by block. Nearblocks doesn't tell in which
+    // order the receipts of one block ran, so those are only ordered by `receipt_id` to
+    // keep replays deterministic. Edits of one RFP in the same block share a snapshot
+    // anyway, the one replayed last wins.
+    pub async fn get_all_rfp_dumps(&self, contract: &str) -> anyhow::Result<Vec<RfpDumpRecord>> {
+        let recs = sqlx::query_as::<_, RfpDumpRecord>(
+            r#"
+            SELECT
+                *
+            FROM
+                rfp_dumps
+            WHERE
+                contract = $1
+            ORDER BY
+                block_height,
+                block_timestamp,
+                receipt_id
+            "#,
+        )
+        .bind(contract)
+        .fetch_all(&self.0)
+        .await?;
+
+        Ok(recs)
+    }
+
+    pub async fn delete_rfp_snapshots(
+        tx: &mut Transaction<'static, Postgres>,
+        contract: &str,
+    ) -> anyhow::Result<u64> {
+        let result = sqlx::query("DELETE FROM rfp_snapshots WHERE contract = $1")
+            .bind(contract)
+            .execute(tx.as_mut())
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    // Replaces the RFP snapshots of `contract` with the ones staged under `staging`
+    pub async fn swap_rfp_snapshots(
+        tx: &mut Transaction<'static, Postgres>,
+        staging: &str,
+        contract: &str,
+    ) -> anyhow::Result<u64> {
+        Self::delete_rfp_snapshots(tx, contract).await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO rfps (contract, id, author_id)
+            SELECT $2, id, author_id FROM rfps WHERE contract = $1
+            ON CONFLICT (contract, id) DO UPDATE SET author_id = EXCLUDED.author_id
+            "#,
+        )
+        .bind(staging)
+        .bind(contract)
+        .execute(tx.as_mut())
+        .await?;
+
+        let result = sqlx::query("UPDATE rfp_snapshots SET contract = $2 WHERE contract = $1")
+            .bind(staging)
+            .bind(contract)
+            .execute(tx.as_mut())
+            .await?;
+
+        sqlx::query("DELETE FROM rfps WHERE contract = $1")
+            .bind(staging)
+            .execute(tx.as_mut())
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
     pub async fn upsert_rfp(
         tx: &mut Transaction<'static, Postgres>,
+        contract: &str,
         rfp_id: u32,
         author_id: String,
     ) -> Result<i32, Error> {
         let rec = sqlx::query!(
             r#"
-          UPDATE rfps SET author_id = $1 WHERE id = $2
+          UPDATE rfps SET author_id = $1 WHERE contract = $2 AND id = $3
           RETURNING id
           "#,
             author_id,
+            contract,
             rfp_id as i32,
         )
         .fetch_optional(tx.as_mut())
@@ -236,11 +599,12 @@ impl DB {
         } else {
             let rec = sqlx::query!(
                 r#"
-                INSERT INTO rfps (id, author_id)
-                VALUES ($1, $2)
-                ON CONFLICT (id) DO NOTHING
+                INSERT INTO rfps (contract, id, author_id)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (contract, id) DO NOTHING
                 RETURNING id
                 "#,
+                contract,
                 rfp_id as i32,
                 author_id
             )
@@ -250,82 +614,158 @@ impl DB {
         }
     }
 
-    // pub async fn upsert_rfp_snapshot(
-    //     tx: &mut Transaction<'static, Postgres>,
-    //     snapshot: &RfpSnapshot,
-    // ) -> anyhow::Result<()> {
-    // Primary key is (rfp_id, ts)
-    //     sqlx::query!(
-    //         r#"
-    //       INSERT INTO rfp_snapshots (
-    //           rfp_id,
-    //           block_height,
-    //           ts,
-    //           editor_id,
-    //           social_db_post_block_height,
-    //           labels,
-    //           linked_proposals,
-    //           rfp_version,
-    //           rfp_body_version,
-    //           name,
-    //           category,
-    //           summary,
-    //           description,
-    //           timeline,
-    //           submission_deadline,
-    //           views
-    //       ) VALUES (
-    //           $1, $2, $3, $4, $5, $6, $7, $8,
-    //           $9, $10, $11, $12, $13, $14, $15, $16
-    //       ) ON CONFLICT (rfp_id, ts) DO UPDATE SET
-    //           block_height = $2,
-    //           editor_id = $4,
-    //           social_db_post_block_height = $5,
-    //           labels = $6,
-    //           linked_proposals = $7,
-    //           rfp_version = $8,
-    //           rfp_body_version = $9,
-    //           name = $10,
-    //           category = $11,
-    //           summary = $12,
-    //           description = $13,
-    //           timeline = $14,
-    //           submission_deadline = $15,
-    //           views = $16
-    //       "#,
-    //         snapshot.rfp_id,
-    //         snapshot.block_height,
-    //         snapshot.ts,
-    //         snapshot.editor_id,
-    //         snapshot.social_db_post_block_height,
-    //         snapshot.labels,
-    //         snapshot.linked_proposals,
-    //         snapshot.rfp_version,
-    //         snapshot.rfp_body_version,
-    //         snapshot.name,
-    //         snapshot.category,
-    //         snapshot.summary,
-    //         snapshot.description,
-    //         snapshot.timeline,
-    //         snapshot.submission_deadline,
-    //         snapshot.views
-    //     )
-    //     .execute(tx)
-    //     .await?;
-    //     Ok(())
-    // }
+    // Primary key is receipt_id, so recording the same receipt twice is a no-op
+    pub async fn insert_rfp_dump(
+        tx: &mut Transaction<'static, Postgres>,
+        dump: &RfpDumpRecord,
+    ) -> anyhow::Result<()> {
+        query!(
+            r#"
+          INSERT INTO rfp_dumps (
+              receipt_id,
+              method_name,
+              block_height,
+              block_timestamp,
+              args,
+              author,
+              rfp_id,
+              contract,
+              succeeded
+          ) VALUES (
+              $1, $2, $3, $4, $5, $6, $7, $8, $9
+          ) ON CONFLICT (receipt_id) DO NOTHING
+          "#,
+            dump.receipt_id,
+            dump.method_name,
+            dump.block_height,
+            dump.block_timestamp,
+            dump.args,
+            dump.author,
+            dump.rfp_id,
+            dump.contract,
+            dump.succeeded
+        )
+        .execute(tx.as_mut())
+        .await?;
+        Ok(())
+    }
+
+    // Latest snapshot of an RFP at or before `ts`, or the latest one overall without `ts`
+    pub async fn get_latest_rfp_snapshot(
+        &self,
+        contract: &str,
+        rfp_id: i32,
+        ts: Option<i64>,
+    ) -> anyhow::Result<Option<RfpSnapshotRecord>> {
+        let rec = sqlx::query_as::<_, RfpSnapshotRecord>(
+            r#"
+            SELECT
+                *
+            FROM
+                rfp_snapshots rs
+            WHERE
+                rs.contract = $3
+                AND rs.rfp_id = $1
+                AND ($2::bigint IS NULL OR rs.ts <= $2)
+            ORDER BY
+                rs.ts DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(rfp_id)
+        .bind(ts)
+        .bind(contract)
+        .fetch_optional(&self.0)
+        .await?;
+
+        Ok(rec)
+    }
+
+    pub async fn upsert_rfp_snapshot(
+        tx: &mut Transaction<'static, Postgres>,
+        snapshot: &RfpSnapshotRecord,
+    ) -> anyhow::Result<()> {
+        // Primary key is (contract, rfp_id, ts)
+        sqlx::query!(
+            r#"
+          INSERT INTO rfp_snapshots (
+              rfp_id,
+              block_height,
+              ts,
+              editor_id,
+              social_db_post_block_height,
+              labels,
+              linked_proposals,
+              rfp_version,
+              rfp_body_version,
+              name,
+              category,
+              summary,
+              description,
+              timeline,
+              submission_deadline,
+              views,
+              contract
+          ) VALUES (
+              $1, $2, $3, $4, $5, $6, $7, $8,
+              $9, $10, $11, $12, $13, $14, $15, $16, $17
+          ) ON CONFLICT (contract, rfp_id, ts) DO UPDATE SET
+              block_height = $2,
+              editor_id = $4,
+              social_db_post_block_height = $5,
+              labels = $6,
+              linked_proposals = $7,
+              rfp_version = $8,
+              rfp_body_version = $9,
+              name = $10,
+              category = $11,
+              summary = $12,
+              description = $13,
+              timeline = $14,
+              submission_deadline = $15,
+              views = $16
+          "#,
+            snapshot.rfp_id,
+            snapshot.block_height,
+            snapshot.ts,
+            snapshot.editor_id,
+            snapshot.social_db_post_block_height,
+            snapshot.labels,
+            snapshot.linked_proposals,
+            snapshot.rfp_version,
+            snapshot.rfp_body_version,
+            snapshot.name,
+            snapshot.category,
+            snapshot.summary,
+            snapshot.description,
+            snapshot.timeline,
+            snapshot.submission_deadline,
+            snapshot.views,
+            snapshot.contract
+        )
+        .execute(tx.as_mut())
+        .await?;
+        Ok(())
+    }
 
     // Function to get proposals with the latest snapshot
 
+    // Returns one page of proposals together with the total number of proposals matching the filters.
+    // With `match_all_labels` a proposal needs every label in `labels`, otherwise any of them.
+    #[allow(clippy::too_many_arguments)]
     pub async fn get_proposals_with_latest_snapshot(
         &self,
+        contract: &str,
         limit: i64,
         order: &str,
         offset: i64,
         filtered_account_id: Option<String>,
         block_timestamp: Option<i64>,
-        stage: Option<String>,
-    ) -> anyhow::Result<Vec<ProposalWithLatestSnapshotView>> {
+        stages: Option<Vec<String>>,
+        labels: Option<Vec<String>>,
+        match_all_labels: bool,
+        category: Option<String>,
+    ) -> anyhow::Result<(Vec<ProposalWithLatestSnapshotView>, i64)> {
         // Validate the order clause to prevent SQL injection
         let order_clause = match order.to_lowercase().as_str() {
             "asc" => "ASC",
@@ -333,23 +773,49 @@ impl DB {
             _ => "DESC", // Default to DESC if the order is not recognized
         };
 
-        // Set 'stage_clause' to None if 'stage' is None
-        let stage_clause: Option<String> = stage.and_then(|s| match s.to_uppercase().as_str() {
-            "DRAFT" => Some("DRAFT".to_string()),
-            "REVIEW" => Some("REVIEW".to_string()),
-            "APPROVED" => Some("APPROVED".to_string()),
-            "REJECTED" => Some("REJECTED".to_string()),
-            "CANCELED" => Some("CANCELLED".to_string()),
-            "APPROVED_CONDITIONALLY" => Some("CONDITIONALLY".to_string()),
-            "PAYMENT_PROCESSING" => Some("PAYMENT".to_string()),
-            "FUNDED" => Some("FUNDED".to_string()),
-            _ => None,
-        });
+        // Empty stage and label lists don't filter anything
+        let stages = stages.filter(|stages| !stages.is_empty());
+        let labels = labels.filter(|labels| !labels.is_empty());
+        // Both operators can use the GIN index on the labels
+        let labels_operator = if match_all_labels { "?&" } else { "?|" };
+
+        // Shared by the page query and the count query so both apply the same filters
+        let from_clause = format!(
+            r#"
+            FROM
+                proposals p
+            INNER JOIN (
+                SELECT
+                    contract,
+                    proposal_id,
+                    MAX(ts) AS max_ts
+                FROM
+                    proposal_snapshots
+                WHERE
+                    contract = $6
+                GROUP BY
+                    contract,
+                    proposal_id
+            ) latest_snapshots ON p.contract = latest_snapshots.contract
+                AND p.id = latest_snapshots.proposal_id
+            INNER JOIN proposal_snapshots ps ON latest_snapshots.contract = ps.contract
+                AND latest_snapshots.proposal_id = ps.proposal_id
+                AND latest_snapshots.max_ts = ps.ts
+            WHERE
+                ($1::varchar IS NULL OR p.author_id = $1)
+                AND ($2::bigint IS NULL OR ps.ts > $2)
+                AND ($3::varchar[] IS NULL OR ps.timeline_status = ANY($3::varchar[]))
+                AND ($4::text[] IS NULL OR ps.labels {} $4::text[])
+                AND ($5::varchar IS NULL OR ps.category = $5)
+            "#,
+            labels_operator,
+        );
 
         // Build the SQL query with the validated order clause
         let sql = format!(
             r#"
             SELECT
+                ps.contract,
                 ps.proposal_id,
                 p.author_id,
                 ps.block_height,
@@ -371,41 +837,41 @@ impl DB {
                 ps.receiver_account,
                 ps.supervisor,
                 ps.timeline,
+                ps.timeline_status,
                 ps.views
-            FROM
-                proposals p
-            INNER JOIN (
-                SELECT
-                    proposal_id,
-                    MAX(ts) AS max_ts
-                FROM
-                    proposal_snapshots
-                GROUP BY
-                    proposal_id
-            ) latest_snapshots ON p.id = latest_snapshots.proposal_id
-            INNER JOIN proposal_snapshots ps ON latest_snapshots.proposal_id = ps.proposal_id
-                AND latest_snapshots.max_ts = ps.ts
-            WHERE
-                ($3 IS NULL OR p.author_id = $3)
-                AND ($4 IS NULL OR ps.ts > $4)
-                AND ($5 IS NULL OR ps.timeline::text ~ $5)
+            {}
             ORDER BY ps.ts {}
-            LIMIT $1 OFFSET $2
+            LIMIT $7 OFFSET $8
             "#,
-            order_clause,
+            from_clause, order_clause,
         );
 
         // Execute the query
         let recs = sqlx::query_as::<_, ProposalWithLatestSnapshotView>(&sql)
+            .bind(filtered_account_id.clone())
+            .bind(block_timestamp)
+            .bind(stages.clone())
+            .bind(labels.clone())
+            .bind(category.clone())
+            .bind(contract)
             .bind(limit)
             .bind(offset)
-            .bind(filtered_account_id)
-            .bind(block_timestamp)
-            .bind(stage_clause)
             .fetch_all(&self.0)
             .await?;
 
-        Ok(recs)
+        let count_sql = format!("SELECT COUNT(*) {}", from_clause);
+
+        let total = sqlx::query_scalar::<_, i64>(&count_sql)
+            .bind(filtered_account_id)
+            .bind(block_timestamp)
+            .bind(stages)
+            .bind(labels)
+            .bind(category)
+            .bind(contract)
+            .fetch_one(&self.0)
+            .await?;
+
+        Ok((recs, total))
     }
 
     // pub async fn get_proposals_with_latest_snapshot(
@@ -459,53 +925,892 @@ impl DB {
 
     // Function to get RFPs with the latest snapshot
 
-    // pub async fn get_rfps_with_latest_snapshot(
-    //     &self,
-    // ) -> anyhow::Result<Vec<RfpWithLatestSnapshot>> {
-    //     let recs = sqlx::query_as!(
-    //         RfpWithLatestSnapshot,
-    //         r#"
-    //       SELECT
-    //         ps.rfp_id,
-    //         p.author_id,
-    //         ps.block_height,
-    //         ps.ts,
-    //         ps.editor_id,
-    //         ps.social_db_post_block_height,
-    //         ps.labels,
-    //         ps.linked_proposals,
-    //         ps.rfp_version,
-    //         ps.rfp_body_version,
-    //         ps.name,
-    //         ps.category,
-    //         ps.summary,
-    //         ps.description,
-    //         ps.timeline,
-    //         ps.views,
-    //         ps.submission_deadline
-    //       FROM
-    //         rfps p
-    //         INNER JOIN (
-    //           SELECT
-    //             rfp_id,
-    //             MAX(ts) AS max_ts
-    //           FROM
-    //             rfp_snapshots
-    //           GROUP BY
-    //             rfp_id
-    //         ) latest_snapshots ON p.id = latest_snapshots.rfp_id
-    //         INNER JOIN rfp_snapshots ps ON latest_snapshots.rfp_id = ps.rfp_id
-    //         AND latest_snapshots.max_ts = ps.ts;
-    //       "#
-    //     )
-    //     .fetch_all(&self.0)
-    //     .await?;
-    //     Ok(recs)
-    // }
+    // Every label used by the latest snapshot of a proposal, most used first
+    pub async fn get_label_counts(&self, contract: &str) -> anyhow::Result<Vec<LabelCount>> {
+        let recs = sqlx::query_as::<_, LabelCount>(
+            r#"
+            SELECT
+                label,
+                COUNT(*) AS count
+            FROM
+                proposals_with_latest_snapshot,
+                jsonb_array_elements_text(labels) AS label
+            WHERE
+                contract = $1
+            GROUP BY
+                label
+            ORDER BY
+                count DESC,
+                label ASC
+            "#,
+        )
+        .bind(contract)
+        .fetch_all(&self.0)
+        .await?;
+
+        Ok(recs)
+    }
+
+    pub async fn upsert_community(
+        tx: &mut Transaction<'static, Postgres>,
+        contract: &str,
+        handle: &str,
+    ) -> anyhow::Result<()> {
+        query!(
+            r#"
+          INSERT INTO communities (contract, handle)
+          VALUES ($1, $2)
+          ON CONFLICT (contract, handle) DO NOTHING
+          "#,
+            contract,
+            handle
+        )
+        .execute(tx.as_mut())
+        .await?;
+        Ok(())
+    }
+
+    pub async fn upsert_community_snapshot(
+        tx: &mut Transaction<'static, Postgres>,
+        snapshot: &CommunitySnapshotRecord,
+    ) -> anyhow::Result<()> {
+        query!(
+            r#"
+          INSERT INTO community_snapshots (
+              handle,
+              block_height,
+              ts,
+              editor_id,
+              deleted,
+              name,
+              tag,
+              description,
+              logo_url,
+              banner_url,
+              bio_markdown,
+              github_handle,
+              telegram_handle,
+              twitter_handle,
+              website_url,
+              admins,
+              addons,
+              contract
+          ) VALUES (
+              $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18
+          ) ON CONFLICT (contract, handle, ts) DO UPDATE SET
+              block_height = $2,
+              editor_id = $4,
+              deleted = $5,
+              name = $6,
+              tag = $7,
+              description = $8,
+              logo_url = $9,
+              banner_url = $10,
+              bio_markdown = $11,
+              github_handle = $12,
+              telegram_handle = $13,
+              twitter_handle = $14,
+              website_url = $15,
+              admins = $16,
+              addons = $17
+          "#,
+            snapshot.handle,
+            snapshot.block_height,
+            snapshot.ts,
+            snapshot.editor_id,
+            snapshot.deleted,
+            snapshot.name,
+            snapshot.tag,
+            snapshot.description,
+            snapshot.logo_url,
+            snapshot.banner_url,
+            snapshot.bio_markdown,
+            snapshot.github_handle,
+            snapshot.telegram_handle,
+            snapshot.twitter_handle,
+            snapshot.website_url,
+            snapshot.admins,
+            snapshot.addons,
+            snapshot.contract
+        )
+        .execute(tx.as_mut())
+        .await?;
+        Ok(())
+    }
+
+    pub async fn upsert_addon(
+        tx: &mut Transaction<'static, Postgres>,
+        contract: &str,
+        addon_id: &str,
+    ) -> anyhow::Result<()> {
+        query!(
+            r#"
+          INSERT INTO addons (contract, id)
+          VALUES ($1, $2)
+          ON CONFLICT (contract, id) DO NOTHING
+          "#,
+            contract,
+            addon_id
+        )
+        .execute(tx.as_mut())
+        .await?;
+        Ok(())
+    }
+
+    pub async fn upsert_addon_snapshot(
+        tx: &mut Transaction<'static, Postgres>,
+        snapshot: &AddonSnapshotRecord,
+    ) -> anyhow::Result<()> {
+        query!(
+            r#"
+          INSERT INTO addon_snapshots (
+              addon_id,
+              block_height,
+              ts,
+              editor_id,
+              title,
+              description,
+              icon,
+              view_widget,
+              configurator_widget,
+              contract
+          ) VALUES (
+              $1, $2, $3, $4, $5, $6, $7, $8, $9, $10
+          ) ON CONFLICT (contract, addon_id, ts) DO UPDATE SET
+              block_height = $2,
+              editor_id = $4,
+              title = $5,
+              description = $6,
+              icon = $7,
+              view_widget = $8,
+              configurator_widget = $9
+          "#,
+            snapshot.addon_id,
+            snapshot.block_height,
+            snapshot.ts,
+            snapshot.editor_id,
+            snapshot.title,
+            snapshot.description,
+            snapshot.icon,
+            snapshot.view_widget,
+            snapshot.configurator_widget,
+            snapshot.contract
+        )
+        .execute(tx.as_mut())
+        .await?;
+        Ok(())
+    }
+
+    pub async fn insert_community_dump(
+        tx: &mut Transaction<'static, Postgres>,
+        dump: &CommunityDumpRecord,
+    ) -> anyhow::Result<()> {
+        query!(
+            r#"
+          INSERT INTO community_dumps (
+              receipt_id,
+              method_name,
+              block_height,
+              block_timestamp,
+              args,
+              author,
+              handle,
+              addon_id,
+              contract
+          ) VALUES (
+              $1, $2, $3, $4, $5, $6, $7, $8, $9
+          ) ON CONFLICT (receipt_id) DO NOTHING
+          "#,
+            dump.receipt_id,
+            dump.method_name,
+            dump.block_height,
+            dump.block_timestamp,
+            dump.args,
+            dump.author,
+            dump.handle,
+            dump.addon_id,
+            dump.contract
+        )
+        .execute(tx.as_mut())
+        .await?;
+        Ok(())
+    }
+
+    // Communities that haven't been deleted, by name
+    pub async fn get_communities_with_latest_snapshot(
+        &self,
+        contract: &str,
+        limit: i64,
+        offset: i64,
+    ) -> anyhow::Result<(Vec<CommunitySnapshotRecord>, i64)> {
+        let recs = sqlx::query_as::<_, CommunitySnapshotRecord>(
+            r#"
+            SELECT
+                *
+            FROM
+                communities_with_latest_snapshot
+            WHERE
+                contract = $1
+                AND NOT deleted
+            ORDER BY
+                name ASC,
+                handle ASC
+            LIMIT $2 OFFSET $3
+            "#,
+        )
+        .bind(contract)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.0)
+        .await?;
+
+        let total = sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT
+                COUNT(*)
+            FROM
+                communities_with_latest_snapshot
+            WHERE
+                contract = $1
+                AND NOT deleted
+            "#,
+        )
+        .bind(contract)
+        .fetch_one(&self.0)
+        .await?;
+
+        Ok((recs, total))
+    }
+
+    pub async fn get_community_with_latest_snapshot(
+        &self,
+        contract: &str,
+        handle: &str,
+    ) -> anyhow::Result<Option<CommunitySnapshotRecord>> {
+        let rec = sqlx::query_as::<_, CommunitySnapshotRecord>(
+            r#"
+            SELECT
+                *
+            FROM
+                communities_with_latest_snapshot
+            WHERE
+                contract = $1
+                AND handle = $2
+                AND NOT deleted
+            "#,
+        )
+        .bind(contract)
+        .bind(handle)
+        .fetch_optional(&self.0)
+        .await?;
+
+        Ok(rec)
+    }
+
+    // The addons of the latest snapshot of a community in the order the community lists them
+    pub async fn get_community_addons(
+        &self,
+        contract: &str,
+        handle: &str,
+    ) -> anyhow::Result<Vec<CommunityAddonView>> {
+        let recs = sqlx::query_as::<_, CommunityAddonView>(
+            r#"
+            SELECT
+                ca.addon ->> 'id' AS id,
+                ca.addon ->> 'addon_id' AS addon_id,
+                ca.addon ->> 'display_name' AS display_name,
+                (ca.addon ->> 'enabled')::boolean AS enabled,
+                ca.addon ->> 'parameters' AS parameters,
+                a.title,
+                a.description,
+                a.icon,
+                a.view_widget,
+                a.configurator_widget
+            FROM
+                communities_with_latest_snapshot c
+                CROSS JOIN LATERAL jsonb_array_elements(c.addons) WITH ORDINALITY AS ca(addon, position)
+                LEFT JOIN addons_with_latest_snapshot a ON a.contract = c.contract
+                    AND a.addon_id = ca.addon ->> 'addon_id'
+            WHERE
+                c.contract = $1
+                AND c.handle = $2
+                AND NOT c.deleted
+            ORDER BY
+                ca.position
+            "#,
+        )
+        .bind(contract)
+        .bind(handle)
+        .fetch_all(&self.0)
+        .await?;
+
+        Ok(recs)
+    }
+
+    // Records a receipt that failed to process, or bumps the attempts if it failed before
+    #[allow(clippy::too_many_arguments)]
+    pub async fn upsert_failed_receipt(
+        &self,
+        receipt_id: &str,
+        contract: &str,
+        method_family: &str,
+        method_name: Option<&str>,
+        block_height: i64,
+        block_timestamp: i64,
+        transaction: &serde_json::Value,
+        error: &str,
+    ) -> anyhow::Result<()> {
+        query!(
+            r#"
+          INSERT INTO failed_receipts (
+              receipt_id,
+              contract,
+              method_family,
+              method_name,
+              block_height,
+              block_timestamp,
+              transaction,
+              error
+          ) VALUES (
+              $1, $2, $3, $4, $5, $6, $7, $8
+          ) ON CONFLICT (receipt_id) DO UPDATE SET
+              error = $8,
+              attempts = failed_receipts.attempts + 1,
+              last_failed_at = now()
+          "#,
+            receipt_id,
+            contract,
+            method_family,
+            method_name,
+            block_height,
+            block_timestamp,
+            transaction,
+            error
+        )
+        .execute(&self.0)
+        .await?;
+        Ok(())
+    }
+
+    // Fewest attempts first, so receipts that keep failing don't hide the rest from a retry
+    pub async fn get_failed_receipts(
+        &self,
+        contract: &str,
+        limit: i64,
+        offset: i64,
+    ) -> anyhow::Result<(Vec<FailedReceiptRecord>, i64)> {
+        let recs = sqlx::query_as::<_, FailedReceiptRecord>(
+            r#"
+            SELECT
+                *
+            FROM
+                failed_receipts
+            WHERE
+                contract = $1
+            ORDER BY
+                attempts ASC,
+                block_height ASC,
+                receipt_id ASC
+            LIMIT $2 OFFSET $3
+            "#,
+        )
+        .bind(contract)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.0)
+        .await?;
+
+        let total = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM failed_receipts WHERE contract = $1",
+        )
+        .bind(contract)
+        .fetch_one(&self.0)
+        .await?;
+
+        Ok((recs, total))
+    }
+
+    pub async fn delete_failed_receipt(&self, receipt_id: &str) -> anyhow::Result<()> {
+        query!(
+            r#"
+          DELETE FROM failed_receipts
+          WHERE receipt_id = $1
+          "#,
+            receipt_id
+        )
+        .execute(&self.0)
+        .await?;
+        Ok(())
+    }
+
+    // Every account that authored a proposal or an RFP, most proposals first
+    pub async fn get_author_summaries(
+        &self,
+        contract: &str,
+        limit: i64,
+        offset: i64,
+    ) -> anyhow::Result<(Vec<AuthorSummary>, i64)> {
+        // Shared by the page query and the count query
+        let authors_cte = r#"
+            WITH proposal_authors AS (
+                SELECT
+                    author_id,
+                    COUNT(*) AS proposal_count,
+                    COUNT(*) FILTER (WHERE timeline_status = 'FUNDED') AS funded_count,
+                    COALESCE(SUM(requested_sponsorship_usd_amount), 0)::bigint AS requested_sponsorship_usd_amount
+                FROM
+                    proposals_with_latest_snapshot
+                WHERE
+                    contract = $1
+                GROUP BY
+                    author_id
+            ),
+            rfp_authors AS (
+                SELECT
+                    author_id,
+                    COUNT(*) AS rfp_count
+                FROM
+                    rfps
+                WHERE
+                    contract = $1
+                GROUP BY
+                    author_id
+            ),
+            authors AS (
+                SELECT
+                    COALESCE(pa.author_id, ra.author_id) AS author_id,
+                    COALESCE(pa.proposal_count, 0) AS proposal_count,
+                    COALESCE(pa.funded_count, 0) AS funded_count,
+                    COALESCE(ra.rfp_count, 0) AS rfp_count,
+                    COALESCE(pa.requested_sponsorship_usd_amount, 0) AS requested_sponsorship_usd_amount
+                FROM
+                    proposal_authors pa
+                    FULL OUTER JOIN rfp_authors ra ON pa.author_id = ra.author_id
+            )
+        "#;
+
+        let sql = format!(
+            r#"
+            {}
+            SELECT
+                *
+            FROM
+                authors
+            ORDER BY
+                proposal_count DESC,
+                author_id ASC
+            LIMIT $2 OFFSET $3
+            "#,
+            authors_cte,
+        );
+
+        let recs = sqlx::query_as::<_, AuthorSummary>(&sql)
+            .bind(contract)
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&self.0)
+            .await?;
+
+        let count_sql = format!("{} SELECT COUNT(*) FROM authors", authors_cte);
+
+        let total = sqlx::query_scalar::<_, i64>(&count_sql)
+            .bind(contract)
+            .fetch_one(&self.0)
+            .await?;
+
+        Ok((recs, total))
+    }
+
+    pub async fn get_proposals_by_author(
+        &self,
+        contract: &str,
+        author_id: &str,
+    ) -> anyhow::Result<Vec<ProposalWithLatestSnapshotView>> {
+        let recs = sqlx::query_as::<_, ProposalWithLatestSnapshotView>(
+            r#"
+            SELECT
+                *
+            FROM
+                proposals_with_latest_snapshot
+            WHERE
+                contract = $1
+                AND author_id = $2
+            ORDER BY
+                ts DESC
+            "#,
+        )
+        .bind(contract)
+        .bind(author_id)
+        .fetch_all(&self.0)
+        .await?;
+
+        Ok(recs)
+    }
+
+    pub async fn get_rfps_by_author(
+        &self,
+        contract: &str,
+        author_id: &str,
+    ) -> anyhow::Result<Vec<RfpWithLatestSnapshotView>> {
+        let recs = sqlx::query_as::<_, RfpWithLatestSnapshotView>(
+            r#"
+            SELECT
+                *
+            FROM
+                rfps_with_latest_snapshot
+            WHERE
+                contract = $1
+                AND author_id = $2
+            ORDER BY
+                ts DESC
+            "#,
+        )
+        .bind(contract)
+        .bind(author_id)
+        .fetch_all(&self.0)
+        .await?;
+
+        Ok(recs)
+    }
+
+    // Proposal and RFP snapshots created by `editor_id`, newest first
+    pub async fn get_edits_by_editor(
+        &self,
+        contract: &str,
+        editor_id: &str,
+        limit: i64,
+    ) -> anyhow::Result<Vec<AuthorEditRecord>> {
+        let recs = sqlx::query_as::<_, AuthorEditRecord>(
+            r#"
+            SELECT
+                'proposal' AS kind,
+                proposal_id AS id,
+                ts,
+                block_height
+            FROM
+                proposal_snapshots
+            WHERE
+                contract = $3
+                AND editor_id = $1
+            UNION ALL
+            SELECT
+                'rfp' AS kind,
+                rfp_id AS id,
+                ts,
+                block_height
+            FROM
+                rfp_snapshots
+            WHERE
+                contract = $3
+                AND editor_id = $1
+            ORDER BY
+                ts DESC
+            LIMIT $2
+            "#,
+        )
+        .bind(editor_id)
+        .bind(limit)
+        .bind(contract)
+        .fetch_all(&self.0)
+        .await?;
+
+        Ok(recs)
+    }
+
+    // Proposals whose latest snapshot names `account_id` as supervisor
+    pub async fn get_supervised_proposal_ids(
+        &self,
+        contract: &str,
+        account_id: &str,
+    ) -> anyhow::Result<Vec<i32>> {
+        let ids = sqlx::query_scalar::<_, i32>(
+            r#"
+            SELECT
+                proposal_id
+            FROM
+                proposals_with_latest_snapshot
+            WHERE
+                contract = $1
+                AND supervisor = $2
+            ORDER BY
+                proposal_id
+            "#,
+        )
+        .bind(contract)
+        .bind(account_id)
+        .fetch_all(&self.0)
+        .await?;
+
+        Ok(ids)
+    }
+
+    // Proposals whose latest snapshot names `account_id` as requested sponsor
+    pub async fn get_sponsored_proposal_ids(
+        &self,
+        contract: &str,
+        account_id: &str,
+    ) -> anyhow::Result<Vec<i32>> {
+        let ids = sqlx::query_scalar::<_, i32>(
+            r#"
+            SELECT
+                proposal_id
+            FROM
+                proposals_with_latest_snapshot
+            WHERE
+                contract = $1
+                AND requested_sponsor = $2
+            ORDER BY
+                proposal_id
+            "#,
+        )
+        .bind(contract)
+        .bind(account_id)
+        .fetch_all(&self.0)
+        .await?;
+
+        Ok(ids)
+    }
+
+    // Number of proposals per stage and the total requested funding of every category,
+    // based on the latest snapshot of each proposal
+    pub async fn get_category_summaries(
+        &self,
+        contract: &str,
+    ) -> anyhow::Result<Vec<CategorySummary>> {
+        let recs = sqlx::query_as::<_, CategorySummary>(
+            r#"
+            WITH latest AS (
+                SELECT
+                    category,
+                    timeline_status AS stage,
+                    requested_sponsorship_usd_amount
+                FROM
+                    proposals_with_latest_snapshot
+                WHERE
+                    contract = $1
+            ),
+            per_stage AS (
+                SELECT
+                    category,
+                    COALESCE(stage, 'UNKNOWN') AS stage,
+                    COUNT(*) AS count
+                FROM
+                    latest
+                GROUP BY
+                    category,
+                    COALESCE(stage, 'UNKNOWN')
+            )
+            SELECT
+                l.category,
+                COUNT(*) AS total,
+                COALESCE(SUM(l.requested_sponsorship_usd_amount), 0)::bigint AS requested_sponsorship_usd_amount,
+                (
+                    SELECT
+                        jsonb_object_agg(ps.stage, ps.count)
+                    FROM
+                        per_stage ps
+                    WHERE
+                        ps.category IS NOT DISTINCT FROM l.category
+                ) AS stages
+            FROM
+                latest l
+            GROUP BY
+                l.category
+            ORDER BY
+                total DESC,
+                l.category ASC
+            "#,
+        )
+        .bind(contract)
+        .fetch_all(&self.0)
+        .await?;
+
+        Ok(recs)
+    }
+
+    // Searches the latest snapshot of every proposal with a websearch style query
+    // (`"quoted phrase" -excluded or`), most relevant first
+    pub async fn search_proposals_with_latest_snapshot(
+        &self,
+        contract: &str,
+        search_query: &str,
+        limit: i64,
+        offset: i64,
+    ) -> anyhow::Result<(Vec<ProposalSearchResult>, i64)> {
+        // Each column is matched separately so the GIN indexes on the snapshots can be used
+        let where_clause = r#"
+                v.contract = $2
+                AND (
+                    to_tsvector('english', name) @@ q.query
+                    OR to_tsvector('english', summary) @@ q.query
+                    OR to_tsvector('english', description) @@ q.query
+                    OR to_tsvector('english', timeline) @@ q.query
+                )
+        "#;
+
+        let sql = format!(
+            r#"
+            SELECT
+                v.*,
+                ts_rank(
+                    setweight(to_tsvector('english', coalesce(name, '')), 'A')
+                        || setweight(to_tsvector('english', coalesce(summary, '')), 'B')
+                        || setweight(to_tsvector('english', coalesce(description, '')), 'C')
+                        || setweight(to_tsvector('english', coalesce(timeline, '{{}}'::jsonb)), 'D'),
+                    q.query
+                ) AS rank,
+                ts_headline(
+                    'english',
+                    concat_ws(' ', name, summary, description),
+                    q.query,
+                    'MaxFragments=2, MaxWords=30, MinWords=10'
+                ) AS headline
+            FROM
+                proposals_with_latest_snapshot v,
+                websearch_to_tsquery('english', $1) AS q(query)
+            WHERE
+                {}
+            ORDER BY rank DESC, ts DESC
+            LIMIT $3 OFFSET $4
+            "#,
+            where_clause,
+        );
+
+        let recs = sqlx::query_as::<_, ProposalSearchResult>(&sql)
+            .bind(search_query)
+            .bind(contract)
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&self.0)
+            .await?;
+
+        let count_sql = format!(
+            r#"
+            SELECT
+                COUNT(*)
+            FROM
+                proposals_with_latest_snapshot v,
+                websearch_to_tsquery('english', $1) AS q(query)
+            WHERE
+                {}
+            "#,
+            where_clause,
+        );
+
+        let total = sqlx::query_scalar::<_, i64>(&count_sql)
+            .bind(search_query)
+            .bind(contract)
+            .fetch_one(&self.0)
+            .await?;
+
+        Ok((recs, total))
+    }
+
+    // Returns one page of RFPs together with the total number of RFPs matching the filters
+    #[allow(clippy::too_many_arguments)]
+    pub async fn get_rfps_with_latest_snapshot(
+        &self,
+        contract: &str,
+        limit: i64,
+        order: &str,
+        offset: i64,
+        filtered_account_id: Option<String>,
+        block_timestamp: Option<i64>,
+        // `status`es of the RFP timeline, see `timeline_status_from_stage`
+        stages: Option<Vec<String>>,
+    ) -> anyhow::Result<(Vec<RfpWithLatestSnapshotView>, i64)> {
+        // Validate the order clause to prevent SQL injection
+        let order_clause = match order.to_lowercase().as_str() {
+            "asc" => "ASC",
+            "desc" => "DESC",
+            _ => "DESC", // Default to DESC if the order is not recognized
+        };
+
+        // An empty stage list doesn't filter anything
+        let stages = stages.filter(|stages| !stages.is_empty());
+
+        let where_clause = r#"
+                ($1::varchar IS NULL OR author_id = $1)
+                AND ($2::bigint IS NULL OR ts > $2)
+                AND ($3::varchar[] IS NULL OR timeline->>'status' = ANY($3::varchar[]))
+                AND contract = $4
+        "#;
+
+        let sql = format!(
+            r#"
+            SELECT
+                *
+            FROM
+                rfps_with_latest_snapshot
+            WHERE
+                {}
+            ORDER BY ts {}
+            LIMIT $5 OFFSET $6
+            "#,
+            where_clause, order_clause,
+        );
+
+        let recs = sqlx::query_as::<_, RfpWithLatestSnapshotView>(&sql)
+            .bind(filtered_account_id.clone())
+            .bind(block_timestamp)
+            .bind(stages.clone())
+            .bind(contract)
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&self.0)
+            .await?;
+
+        let count_sql = format!(
+            r#"
+            SELECT
+                COUNT(*)
+            FROM
+                rfps_with_latest_snapshot
+            WHERE
+                {}
+            "#,
+            where_clause,
+        );
+
+        let total = sqlx::query_scalar::<_, i64>(&count_sql)
+            .bind(filtered_account_id)
+            .bind(block_timestamp)
+            .bind(stages)
+            .bind(contract)
+            .fetch_one(&self.0)
+            .await?;
+
+        Ok((recs, total))
+    }
+
+    pub async fn get_rfp_with_latest_snapshot(
+        &self,
+        contract: &str,
+        rfp_id: i32,
+    ) -> anyhow::Result<Option<RfpWithLatestSnapshotView>> {
+        let rec = sqlx::query_as::<_, RfpWithLatestSnapshotView>(
+            r#"
+            SELECT
+                *
+            FROM
+                rfps_with_latest_snapshot
+            WHERE
+                contract = $1
+                AND rfp_id = $2
+            "#,
+        )
+        .bind(contract)
+        .bind(rfp_id)
+        .fetch_optional(&self.0)
+        .await?;
+
+        Ok(rec)
+    }
 
     // Additional functions can be added as needed
 }
 
+#[allow(clippy::result_large_err)]
 async fn run_migrations(rocket: Rocket<Build>) -> fairing::Result {
     match DB::fetch(&rocket) {
         Some(db) => match migrate!("./migrations").run(&**db).await {
@@ -1,3 +1,5 @@
+use crate::nearblocks_client::types::Transaction;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use utoipa::ToSchema;
@@ -14,12 +16,18 @@ pub struct ProposalRecord {
 }
 
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
-pub struct AfterDate {
-    pub after_date: i64,
+pub struct SyncCursorRecord {
+    pub contract: String,
+    pub method_family: String,
+    pub block_height: BlockHeight,
+    pub block_timestamp: Timestamp,
+    pub receipt_id: Option<String>,
+    pub nearblocks_cursor: Option<String>,
 }
 
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
 pub struct ProposalSnapshotRecord {
+    pub contract: String,
     pub proposal_id: i32,
     pub block_height: BlockHeight,
     pub ts: Timestamp,
@@ -45,17 +53,37 @@ pub struct ProposalSnapshotRecord {
 
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
 pub struct DumpRecord {
+    pub contract: String,
     pub receipt_id: String,
     pub method_name: String,
     pub block_height: BlockHeight,
-    pub block_timestamp: i32,
+    pub block_timestamp: Timestamp,
     pub args: String,
     pub author: String,
-    pub proposal_id: i32,
+    // Unknown for `add_proposal`, the contract assigns it in the callback
+    pub proposal_id: Option<i32>,
+    pub succeeded: bool,
+}
+
+// The receipt a dump was stored from, so it can be processed again
+impl From<&DumpRecord> for Transaction {
+    fn from(dump: &DumpRecord) -> Self {
+        Transaction::from_stored_receipt(
+            &dump.receipt_id,
+            &dump.contract,
+            &dump.author,
+            &dump.method_name,
+            &dump.args,
+            dump.block_height,
+            dump.block_timestamp,
+            dump.succeeded,
+        )
+    }
 }
 
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
 pub struct ProposalWithLatestSnapshotView {
+    pub contract: String,
     pub proposal_id: i32,
     pub author_id: String,
     pub block_height: Option<BlockHeight>,
@@ -77,9 +105,93 @@ pub struct ProposalWithLatestSnapshotView {
     pub receiver_account: Option<String>,
     pub supervisor: Option<String>,
     pub timeline: Option<serde_json::Value>,
+    pub timeline_status: Option<String>,
     pub views: Option<i32>,
 }
 
+impl ProposalWithLatestSnapshotView {
+    // The row the view would have for this snapshot
+    pub fn from_snapshot(author_id: String, snapshot: ProposalSnapshotRecord) -> Self {
+        let timeline_status = snapshot
+            .timeline
+            .as_ref()
+            .and_then(|timeline| timeline.get("status"))
+            .and_then(|status| status.as_str())
+            .map(str::to_string);
+
+        ProposalWithLatestSnapshotView {
+            contract: snapshot.contract,
+            proposal_id: snapshot.proposal_id,
+            author_id,
+            block_height: Some(snapshot.block_height),
+            ts: Some(snapshot.ts),
+            editor_id: Some(snapshot.editor_id),
+            social_db_post_block_height: Some(snapshot.social_db_post_block_height),
+            labels: Some(snapshot.labels),
+            proposal_version: Some(snapshot.proposal_version),
+            proposal_body_version: Some(snapshot.proposal_body_version),
+            name: snapshot.name,
+            category: snapshot.category,
+            summary: snapshot.summary,
+            description: snapshot.description,
+            linked_proposals: snapshot.linked_proposals,
+            linked_rfp: snapshot.linked_rfp,
+            requested_sponsorship_usd_amount: snapshot.requested_sponsorship_usd_amount,
+            requested_sponsorship_paid_in_currency: snapshot.requested_sponsorship_paid_in_currency,
+            requested_sponsor: snapshot.requested_sponsor,
+            receiver_account: snapshot.receiver_account,
+            supervisor: snapshot.supervisor,
+            timeline_status,
+            timeline: snapshot.timeline,
+            views: snapshot.views,
+        }
+    }
+}
+
+// A proposal matching a full-text search, with its relevance and a highlighted snippet
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+pub struct ProposalSearchResult {
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub proposal: ProposalWithLatestSnapshotView,
+    pub rank: f32,
+    pub headline: Option<String>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+pub struct LabelCount {
+    pub label: String,
+    pub count: i64,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+pub struct CategorySummary {
+    pub category: Option<String>,
+    pub total: i64,
+    pub requested_sponsorship_usd_amount: i64,
+    // Number of proposals per timeline status, e.g. `{"REVIEW": 3, "FUNDED": 1}`
+    pub stages: serde_json::Value,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+pub struct AuthorSummary {
+    pub author_id: String,
+    pub proposal_count: i64,
+    pub funded_count: i64,
+    pub rfp_count: i64,
+    pub requested_sponsorship_usd_amount: i64,
+}
+
+// A snapshot of a proposal or RFP that was created by an account
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+pub struct AuthorEditRecord {
+    // `proposal` or `rfp`
+    pub kind: String,
+    pub id: i32,
+    pub ts: Timestamp,
+    pub block_height: Option<BlockHeight>,
+}
+
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
 pub struct RfpRecord {
     pub id: i32,
@@ -88,6 +200,7 @@ pub struct RfpRecord {
 
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
 pub struct RfpSnapshotRecord {
+    pub contract: String,
     pub rfp_id: i32,
     pub block_height: BlockHeight,
     pub ts: Timestamp,
@@ -108,6 +221,7 @@ pub struct RfpSnapshotRecord {
 
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
 pub struct RfpWithLatestSnapshotView {
+    pub contract: String,
     pub rfp_id: i32,
     pub author_id: String,
     pub block_height: BlockHeight,
@@ -129,11 +243,109 @@ pub struct RfpWithLatestSnapshotView {
 
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
 pub struct RfpDumpRecord {
+    pub contract: String,
     pub receipt_id: String,
     pub method_name: String,
     pub block_height: BlockHeight,
-    pub block_timestamp: i32,
+    pub block_timestamp: Timestamp,
     pub args: String,
     pub author: String,
-    pub rfp_id: i32,
+    // Unknown for `add_rfp`, the contract assigns it in the callback
+    pub rfp_id: Option<i32>,
+    pub succeeded: bool,
+}
+
+impl From<&RfpDumpRecord> for Transaction {
+    fn from(dump: &RfpDumpRecord) -> Self {
+        Transaction::from_stored_receipt(
+            &dump.receipt_id,
+            &dump.contract,
+            &dump.author,
+            &dump.method_name,
+            &dump.args,
+            dump.block_height,
+            dump.block_timestamp,
+            dump.succeeded,
+        )
+    }
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+pub struct CommunitySnapshotRecord {
+    pub contract: String,
+    pub handle: String,
+    pub block_height: BlockHeight,
+    pub ts: Timestamp,
+    pub editor_id: String,
+    pub deleted: bool,
+    pub name: Option<String>,
+    pub tag: Option<String>,
+    pub description: Option<String>,
+    pub logo_url: Option<String>,
+    pub banner_url: Option<String>,
+    pub bio_markdown: Option<String>,
+    pub github_handle: Option<String>,
+    pub telegram_handle: Option<String>,
+    pub twitter_handle: Option<String>,
+    pub website_url: Option<String>,
+    pub admins: Option<serde_json::Value>,
+    pub addons: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+pub struct AddonSnapshotRecord {
+    pub contract: String,
+    pub addon_id: String,
+    pub block_height: BlockHeight,
+    pub ts: Timestamp,
+    pub editor_id: String,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub icon: Option<String>,
+    pub view_widget: Option<String>,
+    pub configurator_widget: Option<String>,
+}
+
+// An addon enabled on a community together with the addon it is an instance of
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+pub struct CommunityAddonView {
+    pub id: Option<String>,
+    pub addon_id: Option<String>,
+    pub display_name: Option<String>,
+    pub enabled: Option<bool>,
+    pub parameters: Option<String>,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub icon: Option<String>,
+    pub view_widget: Option<String>,
+    pub configurator_widget: Option<String>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+pub struct CommunityDumpRecord {
+    pub contract: String,
+    pub receipt_id: String,
+    pub method_name: String,
+    pub block_height: BlockHeight,
+    pub block_timestamp: Timestamp,
+    pub args: String,
+    pub author: String,
+    pub handle: Option<String>,
+    pub addon_id: Option<String>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+pub struct FailedReceiptRecord {
+    pub receipt_id: String,
+    pub contract: String,
+    pub method_family: String,
+    pub method_name: Option<String>,
+    pub block_height: BlockHeight,
+    pub block_timestamp: Timestamp,
+    // The nearblocks transaction as it was received
+    pub transaction: serde_json::Value,
+    pub error: String,
+    pub attempts: i32,
+    pub first_failed_at: DateTime<Utc>,
+    pub last_failed_at: DateTime<Utc>,
 }
@@ -1 +1,116 @@
+use super::communities::dump_from_transaction;
+use crate::community::AddOn;
+use crate::config::Config;
+use crate::db::types::{AddonSnapshotRecord, CommunityAddonView};
+use crate::db::DB;
+use crate::error::{Error, Result};
+use crate::nearblocks_client::types::Transaction;
+use crate::sources::{at_block_height, ContractReader};
+use rocket::serde::Deserialize;
+use rocket::{get, serde::json::Json, State};
 
+#[derive(Deserialize)]
+struct PartialAddonArgs {
+    addon: PartialAddOn,
+}
+
+#[derive(Deserialize)]
+struct PartialAddOn {
+    id: String,
+}
+
+fn get_addon_id(transaction: &Transaction) -> Result<String> {
+    let action = transaction
+        .actions
+        .first()
+        .ok_or_else(|| Error::Decode("No actions found in transaction".to_string()))?;
+
+    let args: PartialAddonArgs = action.decode_args()?;
+
+    Ok(args.addon.id)
+}
+
+fn addon_snapshot(addon: AddOn, transaction: &Transaction) -> Result<AddonSnapshotRecord> {
+    Ok(AddonSnapshotRecord {
+        contract: transaction.receiver_account_id.clone(),
+        addon_id: addon.id,
+        block_height: transaction.block.block_height,
+        ts: transaction
+            .block_timestamp
+            .parse::<i64>()
+            .map_err(|e| Error::Decode(format!("Invalid block timestamp: {}", e)))?,
+        editor_id: transaction.predecessor_account_id.clone(),
+        title: Some(addon.title),
+        description: Some(addon.description),
+        icon: Some(addon.icon),
+        view_widget: Some(addon.view_widget),
+        configurator_widget: Some(addon.configurator_widget),
+    })
+}
+
+// Indexes `create_addon` and `update_addon`, called by the community indexer
+pub async fn handle_addon_change(
+    transaction: &Transaction,
+    db: &DB,
+    contract_reader: &dyn ContractReader,
+) -> Result<()> {
+    let addon_id = get_addon_id(transaction)?;
+
+    // The addon as it was right after this receipt, not as it is today
+    let addon = contract_reader
+        .get_addon_at(&addon_id, at_block_height(transaction.block.block_height))
+        .await?
+        .ok_or_else(|| Error::NotFound(format!("Addon {} not found on the contract", addon_id)))?;
+
+    println!("Updating addon in the database... {}", addon_id);
+    let mut tx = db.begin().await?;
+
+    DB::upsert_addon(&mut tx, &transaction.receiver_account_id, &addon_id).await?;
+
+    DB::upsert_addon_snapshot(&mut tx, &addon_snapshot(addon, transaction)?).await?;
+
+    DB::insert_community_dump(
+        &mut tx,
+        &dump_from_transaction(transaction, None, Some(addon_id))?,
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+#[utoipa::path(
+    get,
+    path = "/{contract}/communities/{handle}/addons",
+    responses(Error)
+)]
+#[get("/<contract>/communities/<handle>/addons", rank = 2)]
+async fn get_community_addons(
+    contract: &str,
+    handle: &str,
+    db: &State<DB>,
+    config: &State<Config>,
+) -> Result<Json<Vec<CommunityAddonView>>> {
+    let contract = config.contract(contract)?.as_str();
+
+    if db
+        .get_community_with_latest_snapshot(contract, handle)
+        .await?
+        .is_none()
+    {
+        return Err(Error::NotFound(format!("Community {} not found", handle)));
+    }
+
+    let addons = db.get_community_addons(contract, handle).await?;
+
+    Ok(Json(addons))
+}
+
+pub fn stage() -> rocket::fairing::AdHoc {
+    rocket::fairing::AdHoc::on_ignite("Addons Stage", |rocket| async {
+        println!("Addons stage on ignite!");
+
+        rocket.mount("/", rocket::routes![get_community_addons])
+    })
+}
@@ -0,0 +1,179 @@
+use super::{communities, proposal, rfp};
+use crate::api_background_service::UNDECODED_METHOD_FAMILY;
+use crate::config::Config;
+use crate::db::types::FailedReceiptRecord;
+use crate::db::DB;
+use crate::error::{Error, Result};
+use crate::nearblocks_client::types::Transaction;
+use crate::sources::Sources;
+use crate::types::{clamp_page, PaginatedResponse};
+use near_account_id::AccountId;
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome, Request};
+use rocket::serde::Serialize;
+use rocket::{get, post, serde::json::Json, State};
+use utoipa::ToSchema;
+
+// Receipts retried per call, so one request can't hold the indexer up for long
+const MAX_RETRY_BATCH: i64 = 100;
+
+// Guards the admin endpoints with `Authorization: Bearer <admin_api_key>`.
+// Without `admin_api_key` in the config (ROCKET_ADMIN_API_KEY) they stay disabled.
+pub struct AdminKey;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for AdminKey {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let expected = match req
+            .rocket()
+            .state::<Config>()
+            .and_then(|config| config.admin_api_key.as_deref())
+        {
+            Some(key) if !key.is_empty() => key,
+            _ => return Outcome::Error((Status::Forbidden, ())),
+        };
+
+        let provided = req
+            .headers()
+            .get_one("Authorization")
+            .and_then(|value| value.strip_prefix("Bearer "));
+
+        match provided {
+            Some(key) if key == expected => Outcome::Success(AdminKey),
+            _ => Outcome::Error((Status::Unauthorized, ())),
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(crate = "rocket::serde")]
+pub struct RetryResult {
+    pub retried: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/{contract}/failed-receipts?<limit>&<offset>",
+    responses(Error)
+)]
+#[get("/<contract>/failed-receipts?<limit>&<offset>")]
+async fn get_failed_receipts(
+    _admin: AdminKey,
+    contract: &str,
+    limit: Option<i64>,
+    offset: Option<i64>,
+    db: &State<DB>,
+    config: &State<Config>,
+) -> Result<Json<PaginatedResponse<FailedReceiptRecord>>> {
+    let contract = config.contract(contract)?;
+    let (limit, offset) = clamp_page(limit, offset);
+
+    let (receipts, total) = db
+        .get_failed_receipts(contract.as_str(), limit, offset)
+        .await?;
+
+    Ok(Json(PaginatedResponse::from_offset(
+        receipts, offset, limit, total,
+    )))
+}
+
+async fn retry_receipt(
+    record: &FailedReceiptRecord,
+    contract: &AccountId,
+    db: &DB,
+    sources: &Sources,
+) -> Result<()> {
+    // Fails again for a transaction we still can't decode
+    let transaction: Transaction = serde_json::from_value(record.transaction.clone())?;
+    let transactions = std::slice::from_ref(&transaction);
+    let contract_reader = sources.contract_reader(contract);
+    let contract_reader = contract_reader.as_ref();
+
+    match record.method_family.as_str() {
+        proposal::PROPOSAL_METHOD_FAMILY => {
+            proposal::process_transactions(transactions, db, Some(contract_reader)).await
+        }
+        rfp::RFP_METHOD_FAMILY => {
+            rfp::process_transactions(transactions, db, Some(contract_reader)).await
+        }
+        communities::COMMUNITY_METHOD_FAMILY => {
+            communities::process_transactions(transactions, db, contract_reader).await
+        }
+        // Every family skipped it, the ones it doesn't belong to ignore it
+        UNDECODED_METHOD_FAMILY => {
+            proposal::process_transactions(transactions, db, Some(contract_reader)).await?;
+            rfp::process_transactions(transactions, db, Some(contract_reader)).await?;
+            communities::process_transactions(transactions, db, contract_reader).await
+        }
+        family => Err(Error::BadRequest(format!(
+            "Unknown method family {}",
+            family
+        ))),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/{contract}/failed-receipts/retry",
+    responses(Error)
+)]
+#[post("/<contract>/failed-receipts/retry")]
+async fn retry_failed_receipts(
+    _admin: AdminKey,
+    contract: &str,
+    db: &State<DB>,
+    sources: &State<Sources>,
+    config: &State<Config>,
+) -> Result<Json<RetryResult>> {
+    let contract = config.contract(contract)?;
+    let (receipts, _) = db
+        .get_failed_receipts(contract.as_str(), MAX_RETRY_BATCH, 0)
+        .await?;
+
+    let mut result = RetryResult {
+        retried: receipts.len(),
+        succeeded: 0,
+        failed: 0,
+    };
+
+    for record in receipts.iter() {
+        match retry_receipt(record, contract, db, sources).await {
+            Ok(()) => {
+                db.delete_failed_receipt(&record.receipt_id).await?;
+                result.succeeded += 1;
+            }
+            Err(e) => {
+                eprintln!("Retry of receipt {} failed: {}", record.receipt_id, e);
+                db.upsert_failed_receipt(
+                    &record.receipt_id,
+                    &record.contract,
+                    &record.method_family,
+                    record.method_name.as_deref(),
+                    record.block_height,
+                    record.block_timestamp,
+                    &record.transaction,
+                    &e.to_string(),
+                )
+                .await?;
+                result.failed += 1;
+            }
+        }
+    }
+
+    Ok(Json(result))
+}
+
+pub fn stage() -> rocket::fairing::AdHoc {
+    rocket::fairing::AdHoc::on_ignite("Admin Stage", |rocket| async {
+        println!("Admin stage on ignite!");
+
+        rocket.mount(
+            "/admin/",
+            rocket::routes![get_failed_receipts, retry_failed_receipts],
+        )
+    })
+}
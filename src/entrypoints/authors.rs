@@ -0,0 +1,70 @@
+use crate::config::Config;
+use crate::db::types::AuthorSummary;
+use crate::db::DB;
+use crate::error::{Error, Result};
+use crate::types::{clamp_page, AuthorResponse, PaginatedResponse};
+use rocket::{get, serde::json::Json, State};
+
+// Keeps the response small for accounts that edit a lot
+const MAX_AUTHOR_EDITS: i64 = 100;
+
+#[utoipa::path(get, path = "/{contract}/authors?<limit>&<offset>", responses(Error))]
+#[get("/<contract>/authors?<limit>&<offset>")]
+async fn get_authors(
+    contract: &str,
+    limit: Option<i64>,
+    offset: Option<i64>,
+    db: &State<DB>,
+    config: &State<Config>,
+) -> Result<Json<PaginatedResponse<AuthorSummary>>> {
+    let contract = config.contract(contract)?.as_str();
+    let (limit, offset) = clamp_page(limit, offset);
+
+    let (authors, total) = db.get_author_summaries(contract, limit, offset).await?;
+
+    Ok(Json(PaginatedResponse::from_offset(
+        authors, offset, limit, total,
+    )))
+}
+
+#[utoipa::path(get, path = "/{contract}/authors/{account_id}", responses(Error))]
+#[get("/<contract>/authors/<account_id>", rank = 2)]
+async fn get_author(
+    contract: &str,
+    account_id: &str,
+    db: &State<DB>,
+    config: &State<Config>,
+) -> Result<Json<AuthorResponse>> {
+    let contract = config.contract(contract)?.as_str();
+
+    let author = AuthorResponse {
+        author_id: account_id.to_string(),
+        proposals: db.get_proposals_by_author(contract, account_id).await?,
+        rfps: db.get_rfps_by_author(contract, account_id).await?,
+        edits: db
+            .get_edits_by_editor(contract, account_id, MAX_AUTHOR_EDITS)
+            .await?,
+        supervisor_of: db.get_supervised_proposal_ids(contract, account_id).await?,
+        requested_sponsor_of: db.get_sponsored_proposal_ids(contract, account_id).await?,
+    };
+
+    // An account we have never seen anywhere
+    if author.proposals.is_empty()
+        && author.rfps.is_empty()
+        && author.edits.is_empty()
+        && author.supervisor_of.is_empty()
+        && author.requested_sponsor_of.is_empty()
+    {
+        return Err(Error::NotFound(format!("Author {} not found", account_id)));
+    }
+
+    Ok(Json(author))
+}
+
+pub fn stage() -> rocket::fairing::AdHoc {
+    rocket::fairing::AdHoc::on_ignite("Authors Stage", |rocket| async {
+        println!("Authors stage on ignite!");
+
+        rocket.mount("/", rocket::routes![get_authors, get_author])
+    })
+}
@@ -0,0 +1,27 @@
+use crate::config::Config;
+use crate::db::types::CategorySummary;
+use crate::db::DB;
+use crate::error::{Error, Result};
+use rocket::{get, serde::json::Json, State};
+
+#[utoipa::path(get, path = "/{contract}/categories", responses(Error))]
+#[get("/<contract>/categories", rank = 2)]
+async fn get_categories(
+    contract: &str,
+    db: &State<DB>,
+    config: &State<Config>,
+) -> Result<Json<Vec<CategorySummary>>> {
+    let contract = config.contract(contract)?;
+
+    let categories = db.get_category_summaries(contract.as_str()).await?;
+
+    Ok(Json(categories))
+}
+
+pub fn stage() -> rocket::fairing::AdHoc {
+    rocket::fairing::AdHoc::on_ignite("Categories Stage", |rocket| async {
+        println!("Categories stage on ignite!");
+
+        rocket.mount("/", rocket::routes![get_categories])
+    })
+}
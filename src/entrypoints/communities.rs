@@ -0,0 +1,267 @@
+use super::addons;
+use crate::api_background_service::{process_and_advance, unprocessed_txns};
+use crate::community::Community;
+use crate::config::Config;
+use crate::db::types::{CommunityDumpRecord, CommunitySnapshotRecord};
+use crate::db::DB;
+use crate::error::{Error, Result};
+use crate::nearblocks_client::types::Transaction;
+use crate::sources::{at_block_height, ContractReader, Sources};
+use crate::types::{clamp_page, PaginatedResponse};
+use near_account_id::AccountId;
+use rocket::serde::Deserialize;
+use rocket::{get, serde::json::Json, State};
+
+// Key of the community and addon methods in the `sync_cursors` table
+pub const COMMUNITY_METHOD_FAMILY: &str = "community";
+
+// `create_community` passes the handle inside `inputs`, the other methods pass it directly
+#[derive(Deserialize)]
+struct PartialCommunityArgs {
+    handle: Option<String>,
+    inputs: Option<PartialCommunityInputs>,
+}
+
+#[derive(Deserialize)]
+struct PartialCommunityInputs {
+    handle: String,
+}
+
+fn get_community_handle(transaction: &Transaction) -> Result<String> {
+    let action = transaction
+        .actions
+        .first()
+        .ok_or_else(|| Error::Decode("No actions found in transaction".to_string()))?;
+
+    let args: PartialCommunityArgs = action.decode_args()?;
+
+    args.handle
+        .or(args.inputs.map(|inputs| inputs.handle))
+        .ok_or_else(|| Error::Decode("No community handle found in arguments".to_string()))
+}
+
+pub fn dump_from_transaction(
+    transaction: &Transaction,
+    handle: Option<String>,
+    addon_id: Option<String>,
+) -> Result<CommunityDumpRecord> {
+    let action = transaction.actions.first();
+    Ok(CommunityDumpRecord {
+        // Receipts are executed by the contract they were sent to
+        contract: transaction.receiver_account_id.clone(),
+        receipt_id: transaction.receipt_id.clone(),
+        method_name: action.map(|a| a.method.clone()).unwrap_or_default(),
+        block_height: transaction.block.block_height,
+        block_timestamp: transaction
+            .block_timestamp
+            .parse::<i64>()
+            .map_err(|e| Error::Decode(format!("Invalid block timestamp: {}", e)))?,
+        args: action.map(|a| a.args.clone()).unwrap_or_default(),
+        author: transaction.predecessor_account_id.clone(),
+        handle,
+        addon_id,
+    })
+}
+
+// Builds the snapshot of `handle` after `transaction`, `None` means the community was deleted
+fn community_snapshot(
+    handle: String,
+    community: Option<Community>,
+    transaction: &Transaction,
+) -> Result<CommunitySnapshotRecord> {
+    let ts = transaction
+        .block_timestamp
+        .parse::<i64>()
+        .map_err(|e| Error::Decode(format!("Invalid block timestamp: {}", e)))?;
+    let contract = transaction.receiver_account_id.clone();
+    let editor_id = transaction.predecessor_account_id.clone();
+    let block_height = transaction.block.block_height;
+
+    Ok(match community {
+        Some(community) => CommunitySnapshotRecord {
+            contract,
+            handle,
+            block_height,
+            ts,
+            editor_id,
+            deleted: false,
+            name: Some(community.name),
+            tag: Some(community.tag),
+            description: Some(community.description),
+            logo_url: Some(community.logo_url),
+            banner_url: Some(community.banner_url),
+            bio_markdown: community.bio_markdown,
+            github_handle: community.github_handle,
+            telegram_handle: community.telegram_handle,
+            twitter_handle: community.twitter_handle,
+            website_url: community.website_url,
+            admins: serde_json::to_value(&community.admins).ok(),
+            addons: serde_json::to_value(&community.addons).ok(),
+        },
+        None => CommunitySnapshotRecord {
+            contract,
+            handle,
+            block_height,
+            ts,
+            editor_id,
+            deleted: true,
+            name: None,
+            tag: None,
+            description: None,
+            logo_url: None,
+            banner_url: None,
+            bio_markdown: None,
+            github_handle: None,
+            telegram_handle: None,
+            twitter_handle: None,
+            website_url: None,
+            admins: None,
+            addons: None,
+        },
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/{contract}/communities?<limit>&<offset>",
+    responses(Error)
+)]
+#[get("/<contract>/communities?<limit>&<offset>")]
+async fn get_communities(
+    contract: &str,
+    limit: Option<i64>,
+    offset: Option<i64>,
+    db: &State<DB>,
+    config: &State<Config>,
+) -> Result<Json<PaginatedResponse<CommunitySnapshotRecord>>> {
+    let contract = config.contract(contract)?;
+    let (limit, offset) = clamp_page(limit, offset);
+
+    let (communities, total) = db
+        .get_communities_with_latest_snapshot(contract.as_str(), limit, offset)
+        .await?;
+
+    Ok(Json(PaginatedResponse::from_offset(
+        communities,
+        offset,
+        limit,
+        total,
+    )))
+}
+
+#[utoipa::path(get, path = "/{contract}/communities/{handle}", responses(Error))]
+#[get("/<contract>/communities/<handle>", rank = 2)]
+async fn get_community(
+    contract: &str,
+    handle: &str,
+    db: &State<DB>,
+    config: &State<Config>,
+) -> Result<Json<CommunitySnapshotRecord>> {
+    let contract = config.contract(contract)?;
+
+    db.get_community_with_latest_snapshot(contract.as_str(), handle)
+        .await?
+        .map(Json)
+        .ok_or_else(|| Error::NotFound(format!("Community {} not found", handle)))
+}
+
+// Called by the background indexer, see `entrypoints::sync`
+pub async fn sync_communities(
+    db: &DB,
+    sources: &Sources,
+    contract: &AccountId,
+    transactions: &[Transaction],
+) -> anyhow::Result<()> {
+    let contract_reader = sources.contract_reader(contract);
+    let contract = contract.as_str();
+    let transactions =
+        unprocessed_txns(db, contract, COMMUNITY_METHOD_FAMILY, transactions).await?;
+
+    let contract_reader = contract_reader.as_ref();
+    process_and_advance(
+        db,
+        contract,
+        COMMUNITY_METHOD_FAMILY,
+        transactions,
+        |transaction| async move {
+            process_transactions(std::slice::from_ref(&transaction), db, contract_reader).await
+        },
+    )
+    .await
+}
+
+pub async fn process_transactions(
+    transactions: &[Transaction],
+    db: &DB,
+    contract_reader: &dyn ContractReader,
+) -> Result<()> {
+    for transaction in transactions.iter() {
+        // A failed receipt didn't change the community or addon, there is nothing to read
+        if !transaction.receipt_outcome.status {
+            continue;
+        }
+
+        if let Some(action) = transaction.actions.first() {
+            let result = match action.method.as_str() {
+                "create_community"
+                | "update_community"
+                | "delete_community"
+                | "update_community_board"
+                | "update_community_github"
+                | "set_community_addons"
+                | "set_community_addon" => {
+                    handle_community_change(transaction, db, contract_reader).await
+                }
+                "create_addon" | "update_addon" => {
+                    addons::handle_addon_change(transaction, db, contract_reader).await
+                }
+                // Proposal and RFP methods are handled by their own indexers
+                _ => continue,
+            };
+            result?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn handle_community_change(
+    transaction: &Transaction,
+    db: &DB,
+    contract_reader: &dyn ContractReader,
+) -> Result<()> {
+    let handle = get_community_handle(transaction)?;
+
+    // The arguments of the community methods differ a lot, so we read the community from
+    // the contract as it was right after this receipt
+    let community = contract_reader
+        .get_community_at(&handle, at_block_height(transaction.block.block_height))
+        .await?;
+
+    println!("Updating community in the database... {}", handle);
+    let mut tx = db.begin().await?;
+
+    DB::upsert_community(&mut tx, &transaction.receiver_account_id, &handle).await?;
+
+    let snapshot = community_snapshot(handle.clone(), community, transaction)?;
+
+    DB::upsert_community_snapshot(&mut tx, &snapshot).await?;
+
+    DB::insert_community_dump(
+        &mut tx,
+        &dump_from_transaction(transaction, Some(handle), None)?,
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+pub fn stage() -> rocket::fairing::AdHoc {
+    rocket::fairing::AdHoc::on_ignite("Communities Stage", |rocket| async {
+        println!("Communities stage on ignite!");
+
+        rocket.mount("/", rocket::routes![get_communities, get_community])
+    })
+}
@@ -0,0 +1,27 @@
+use crate::config::Config;
+use crate::db::types::LabelCount;
+use crate::db::DB;
+use crate::error::{Error, Result};
+use rocket::{get, serde::json::Json, State};
+
+#[utoipa::path(get, path = "/{contract}/labels", responses(Error))]
+#[get("/<contract>/labels", rank = 2)]
+async fn get_labels(
+    contract: &str,
+    db: &State<DB>,
+    config: &State<Config>,
+) -> Result<Json<Vec<LabelCount>>> {
+    let contract = config.contract(contract)?;
+
+    let labels = db.get_label_counts(contract.as_str()).await?;
+
+    Ok(Json(labels))
+}
+
+pub fn stage() -> rocket::fairing::AdHoc {
+    rocket::fairing::AdHoc::on_ignite("Labels Stage", |rocket| async {
+        println!("Labels stage on ignite!");
+
+        rocket.mount("/", rocket::routes![get_labels])
+    })
+}
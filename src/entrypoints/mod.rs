@@ -1,10 +1,26 @@
 use rocket::fairing::AdHoc;
+use rocket::http::uri::Origin;
 use utoipa::OpenApi;
+pub mod addons;
+pub mod admin;
+pub mod authors;
+pub mod categories;
+pub mod communities;
+pub mod labels;
 pub mod proposal;
-// pub mod rfp;
-use crate::db::types::ProposalWithLatestSnapshotView;
+pub mod rfp;
+use crate::db::types::{
+    AuthorEditRecord, AuthorSummary, CategorySummary, CommunityAddonView, CommunitySnapshotRecord,
+    FailedReceiptRecord, LabelCount, ProposalSearchResult, ProposalSnapshotRecord,
+    ProposalWithLatestSnapshotView, RfpWithLatestSnapshotView,
+};
 
-use devhub_cache_api::types;
+use crate::api_background_service::fetch_unprocessed_txns;
+use crate::config::Config;
+use crate::db::DB;
+use crate::error::Problem;
+use crate::sources::Sources;
+use crate::types;
 
 #[derive(OpenApi)]
 #[openapi(
@@ -13,10 +29,45 @@ use devhub_cache_api::types;
         version = "0.0.1",
     ),
     paths(
-      proposal::get_proposals
+      proposal::get_proposals,
+      proposal::get_proposal,
+      proposal::search_proposals,
+      proposal::get_proposal_history,
+      proposal::get_proposal_diff,
+      rfp::get_rfps,
+      rfp::get_rfp,
+      labels::get_labels,
+      categories::get_categories,
+      authors::get_authors,
+      authors::get_author,
+      communities::get_communities,
+      communities::get_community,
+      addons::get_community_addons,
+      admin::get_failed_receipts,
+      admin::retry_failed_receipts
     ),
     components(schemas(
-      types::PaginatedResponse<ProposalWithLatestSnapshotView>
+      types::PaginatedResponse<ProposalWithLatestSnapshotView>,
+      types::PaginatedResponse<ProposalSnapshotRecord>,
+      types::PaginatedResponse<ProposalSearchResult>,
+      types::PaginatedResponse<RfpWithLatestSnapshotView>,
+      proposal::diff::ProposalDiff,
+      proposal::diff::SnapshotRef,
+      proposal::diff::FieldChange,
+      proposal::diff::FieldDiff,
+      proposal::diff::LineChange,
+      LabelCount,
+      CategorySummary,
+      types::PaginatedResponse<AuthorSummary>,
+      types::AuthorResponse,
+      AuthorEditRecord,
+      types::PaginatedResponse<CommunitySnapshotRecord>,
+      CommunitySnapshotRecord,
+      CommunityAddonView,
+      types::PaginatedResponse<FailedReceiptRecord>,
+      FailedReceiptRecord,
+      admin::RetryResult,
+      Problem
     )),
     tags(
         (name = "Devhub Cache", description = "Devhub cache endpoints.")
@@ -24,9 +75,86 @@ use devhub_cache_api::types;
 )]
 pub struct ApiDoc;
 
+// First path segment of the routes that are served per contract. Those routes start with
+// `/<contract>`, the ones without a query are ranked 2 so they don't collide with `/swagger-ui/<_..>`.
+const CONTRACT_SCOPED_ROUTES: &[&str] = &[
+    "proposals",
+    "rfps",
+    "labels",
+    "categories",
+    "authors",
+    "communities",
+];
+
+// Called by the background indexer, see `ApiBackgroundService`
+pub async fn sync(db: &DB, config: &Config, sources: &Sources) -> anyhow::Result<()> {
+    let mut result = Ok(());
+    for contract in config.contracts.iter() {
+        // Every method family reads the same account history, so it is fetched only once
+        let transactions = match fetch_unprocessed_txns(
+            db,
+            sources.transactions(),
+            contract.as_str(),
+            &[
+                proposal::PROPOSAL_METHOD_FAMILY,
+                rfp::RFP_METHOD_FAMILY,
+                communities::COMMUNITY_METHOD_FAMILY,
+            ],
+        )
+        .await
+        {
+            Ok(transactions) => transactions,
+            Err(e) => {
+                result = result.and(Err(e));
+                continue;
+            }
+        };
+
+        // A failing proposal sync shouldn't hold back the RFPs, or the other contracts
+        let proposals = proposal::sync_proposals(db, sources, contract, &transactions).await;
+        let rfps = rfp::sync_rfps(db, sources, contract, &transactions).await;
+        let communities = communities::sync_communities(db, sources, contract, &transactions).await;
+        result = result.and(proposals).and(rfps).and(communities);
+    }
+    result
+}
+
+// Routes used to be served without a contract, `/proposals/1` is now `/devhub.near/proposals/1`.
+// Keeps the old paths working by sending them to the default contract.
+fn unscoped_routes() -> AdHoc {
+    AdHoc::on_request("Unscoped routes", |req, _| {
+        Box::pin(async move {
+            let scoped = req
+                .uri()
+                .path()
+                .segments()
+                .next()
+                .is_some_and(|segment| CONTRACT_SCOPED_ROUTES.contains(&segment));
+            if !scoped {
+                return;
+            }
+
+            if let Some(config) = req.rocket().state::<Config>() {
+                let uri = format!("/{}{}", config.default_contract(), req.uri());
+                if let Ok(uri) = Origin::parse_owned(uri) {
+                    req.set_uri(uri);
+                }
+            }
+        })
+    })
+}
+
 pub fn stage() -> AdHoc {
     AdHoc::on_ignite("Installing entrypoints", |rocket| async {
-        rocket.attach(proposal::stage())
-        // .attach(rfp::stage())
+        rocket
+            .attach(unscoped_routes())
+            .attach(proposal::stage())
+            .attach(rfp::stage())
+            .attach(labels::stage())
+            .attach(categories::stage())
+            .attach(authors::stage())
+            .attach(communities::stage())
+            .attach(addons::stage())
+            .attach(admin::stage())
     })
 }
@@ -0,0 +1,285 @@
+use crate::db::types::{ProposalSnapshotRecord, Timestamp};
+use rocket::serde::{Deserialize, Serialize};
+use serde_json::Value;
+use similar::{ChangeTag, TextDiff};
+use utoipa::ToSchema;
+
+// Compared line by line
+const TEXT_FIELDS: [&str; 2] = ["summary", "description"];
+// Compared as JSON documents
+const JSON_FIELDS: [&str; 3] = ["labels", "timeline", "linked_proposals"];
+// Compared as plain values
+const VALUE_FIELDS: [&str; 10] = [
+    "name",
+    "category",
+    "linked_rfp",
+    "requested_sponsorship_usd_amount",
+    "requested_sponsorship_paid_in_currency",
+    "requested_sponsor",
+    "receiver_account",
+    "supervisor",
+    "proposal_version",
+    "proposal_body_version",
+];
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct ProposalDiff {
+    pub proposal_id: i32,
+    pub from: SnapshotRef,
+    pub to: SnapshotRef,
+    pub changes: Vec<FieldChange>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct SnapshotRef {
+    pub ts: Timestamp,
+    pub block_height: i64,
+    pub editor_id: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct FieldChange {
+    pub field: String,
+    #[serde(flatten)]
+    pub diff: FieldDiff,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FieldDiff {
+    // Only the inserted and deleted lines, with their line numbers in the old and new text
+    Text { lines: Vec<LineChange> },
+    // RFC 6902 JSON patch that turns the old value into the new one
+    Json { patch: Value },
+    Value { from: Value, to: Value },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct LineChange {
+    pub tag: String,
+    pub old_line: Option<usize>,
+    pub new_line: Option<usize>,
+    pub value: String,
+}
+
+impl From<&ProposalSnapshotRecord> for SnapshotRef {
+    fn from(snapshot: &ProposalSnapshotRecord) -> Self {
+        SnapshotRef {
+            ts: snapshot.ts,
+            block_height: snapshot.block_height,
+            editor_id: snapshot.editor_id.clone(),
+        }
+    }
+}
+
+pub fn diff_snapshots(from: &ProposalSnapshotRecord, to: &ProposalSnapshotRecord) -> ProposalDiff {
+    let mut old = serde_json::to_value(from).unwrap_or_default();
+    let mut new = serde_json::to_value(to).unwrap_or_default();
+    // Labels are a set in the contract, older snapshots stored them in any order
+    sort_labels(&mut old);
+    sort_labels(&mut new);
+    let mut changes = vec![];
+
+    for field in VALUE_FIELDS {
+        let (old_value, new_value) = (&old[field], &new[field]);
+        if old_value != new_value {
+            changes.push(FieldChange {
+                field: field.to_string(),
+                diff: FieldDiff::Value {
+                    from: old_value.clone(),
+                    to: new_value.clone(),
+                },
+            });
+        }
+    }
+
+    for field in TEXT_FIELDS {
+        let old_text = old[field].as_str().unwrap_or_default();
+        let new_text = new[field].as_str().unwrap_or_default();
+        if old_text != new_text {
+            changes.push(FieldChange {
+                field: field.to_string(),
+                diff: FieldDiff::Text {
+                    lines: diff_lines(old_text, new_text),
+                },
+            });
+        }
+    }
+
+    for field in JSON_FIELDS {
+        let (old_value, new_value) = (&old[field], &new[field]);
+        if old_value != new_value {
+            let patch = json_patch::diff(old_value, new_value);
+            changes.push(FieldChange {
+                field: field.to_string(),
+                diff: FieldDiff::Json {
+                    patch: serde_json::to_value(patch).unwrap_or_default(),
+                },
+            });
+        }
+    }
+
+    ProposalDiff {
+        proposal_id: to.proposal_id,
+        from: from.into(),
+        to: to.into(),
+        changes,
+    }
+}
+
+fn sort_labels(snapshot: &mut Value) {
+    if let Some(labels) = snapshot["labels"].as_array_mut() {
+        labels.sort_by(|a, b| a.as_str().cmp(&b.as_str()));
+    }
+}
+
+fn diff_lines(old: &str, new: &str) -> Vec<LineChange> {
+    TextDiff::from_lines(old, new)
+        .iter_all_changes()
+        .filter_map(|change| {
+            let tag = match change.tag() {
+                ChangeTag::Equal => return None,
+                ChangeTag::Delete => "delete",
+                ChangeTag::Insert => "insert",
+            };
+            Some(LineChange {
+                tag: tag.to_string(),
+                // Line numbers start at 1 like in any editor
+                old_line: change.old_index().map(|i| i + 1),
+                new_line: change.new_index().map(|i| i + 1),
+                value: change.value().trim_end_matches('\n').to_string(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn snapshot(ts: Timestamp) -> ProposalSnapshotRecord {
+        ProposalSnapshotRecord {
+            contract: "devhub.near".to_string(),
+            proposal_id: 1,
+            block_height: ts,
+            ts,
+            editor_id: "alice.near".to_string(),
+            social_db_post_block_height: 1,
+            labels: json!(["a", "b"]),
+            proposal_version: "V0".to_string(),
+            proposal_body_version: "V2".to_string(),
+            name: Some("Proposal".to_string()),
+            category: Some("Marketing".to_string()),
+            summary: Some("summary".to_string()),
+            description: Some("one\ntwo\nthree\n".to_string()),
+            linked_proposals: Some(json!([])),
+            linked_rfp: None,
+            requested_sponsorship_usd_amount: Some(100),
+            requested_sponsorship_paid_in_currency: Some("USDC".to_string()),
+            requested_sponsor: Some("neardevdao.near".to_string()),
+            receiver_account: Some("alice.near".to_string()),
+            supervisor: None,
+            timeline: Some(json!({ "timeline_version": "V1", "status": "DRAFT" })),
+            views: None,
+        }
+    }
+
+    fn change<'a>(diff: &'a ProposalDiff, field: &str) -> &'a FieldDiff {
+        &diff
+            .changes
+            .iter()
+            .find(|change| change.field == field)
+            .unwrap_or_else(|| panic!("no change of {}", field))
+            .diff
+    }
+
+    #[test]
+    fn numbers_changed_lines_from_one() {
+        let lines = diff_lines("one\ntwo\nthree\n", "one\n2\nthree\nfour\n");
+        let lines: Vec<_> = lines
+            .iter()
+            .map(|line| {
+                (
+                    line.tag.as_str(),
+                    line.old_line,
+                    line.new_line,
+                    line.value.as_str(),
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            lines,
+            vec![
+                ("delete", Some(2), None, "two"),
+                ("insert", None, Some(2), "2"),
+                ("insert", None, Some(4), "four"),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_value_fields_with_old_and_new_value() {
+        let from = snapshot(1);
+        let mut to = snapshot(2);
+        to.name = Some("Renamed".to_string());
+        to.linked_rfp = Some(3);
+
+        let diff = diff_snapshots(&from, &to);
+
+        assert_eq!(diff.changes.len(), 2);
+        match change(&diff, "name") {
+            FieldDiff::Value { from, to } => {
+                assert_eq!(from, &json!("Proposal"));
+                assert_eq!(to, &json!("Renamed"));
+            }
+            diff => panic!("unexpected diff {:?}", diff),
+        }
+        match change(&diff, "linked_rfp") {
+            FieldDiff::Value { from, to } => {
+                assert_eq!(from, &Value::Null);
+                assert_eq!(to, &json!(3));
+            }
+            diff => panic!("unexpected diff {:?}", diff),
+        }
+    }
+
+    #[test]
+    fn reports_timeline_as_json_patch() {
+        let from = snapshot(1);
+        let mut to = snapshot(2);
+        to.timeline = Some(json!({
+            "timeline_version": "V1",
+            "status": "REVIEW",
+            "sponsor_requested_review": false,
+        }));
+
+        let diff = diff_snapshots(&from, &to);
+
+        assert_eq!(diff.changes.len(), 1);
+        match change(&diff, "timeline") {
+            FieldDiff::Json { patch } => assert_eq!(
+                patch,
+                &json!([
+                    { "op": "add", "path": "/sponsor_requested_review", "value": false },
+                    { "op": "replace", "path": "/status", "value": "REVIEW" },
+                ])
+            ),
+            diff => panic!("unexpected diff {:?}", diff),
+        }
+    }
+
+    #[test]
+    fn ignores_the_order_of_labels() {
+        let from = snapshot(1);
+        let mut to = snapshot(2);
+        to.labels = json!(["b", "a"]);
+
+        let diff = diff_snapshots(&from, &to);
+
+        assert!(diff.changes.is_empty(), "{:?}", diff.changes);
+        assert_eq!(diff.from.ts, 1);
+        assert_eq!(diff.to.ts, 2);
+    }
+}
@@ -1,177 +1,259 @@
-use devhub_cache_api::db::types::{ProposalSnapshotRecord, ProposalWithLatestSnapshotView};
-use devhub_cache_api::db::DB;
-use devhub_cache_api::nearblocks_client::types::Transaction;
-use devhub_cache_api::rpc_service::RpcService;
-use devhub_cache_api::types::PaginatedResponse;
-use devhub_cache_api::{nearblocks_client, timestamp_to_date_string};
-use devhub_shared::proposal::VersionedProposal;
+use crate::api_background_service::{process_and_advance, unprocessed_txns};
+use crate::config::Config;
+use crate::db::types::{
+    DumpRecord, ProposalSearchResult, ProposalSnapshotRecord, ProposalWithLatestSnapshotView,
+};
+use crate::db::DB;
+use crate::error::{Error, Result};
+use crate::nearblocks_client::types::Transaction;
+use crate::sources::{at_block_height, ContractReader, Sources};
+use crate::types::{clamp_page, PaginatedResponse};
+use devhub_shared::proposal::Proposal as ContractProposal;
 use near_account_id::AccountId;
 use rocket::serde::json::Json;
-use rocket::{get, http::Status, State};
-use std::convert::TryInto;
+use rocket::{get, State};
 
+pub mod diff;
 pub mod types;
+use self::diff::ProposalDiff;
 use self::types::*;
 
+// Key of the proposal methods in the `sync_cursors` table
+pub const PROPOSAL_METHOD_FAMILY: &str = "proposal";
+
+// Every receipt calling one of these is stored in `dumps`, failed ones included
+const PROPOSAL_METHODS: &[&str] = &[
+    "add_proposal",
+    "set_block_height_callback",
+    "edit_proposal",
+    "edit_proposal_timeline",
+    "edit_proposal_versioned_timeline",
+    "edit_proposal_linked_rfp",
+];
+
 // add query params to get_proposals entrypoint
 #[utoipa::path(
     get,
-    path = "/proposals?<order>&<limit>&<offset>&<filtered_account_id>&<block_timestamp>&<stage>"
+    path = "/{contract}/proposals?<order>&<limit>&<offset>&<filtered_account_id>&<block_timestamp>&<stage>&<labels>&<label_match>&<category>",
+    responses(Error)
 )]
-#[get("/?<order>&<limit>&<offset>&<filtered_account_id>&<block_timestamp>&<stage>")]
-// Json<Proposal>
+#[get("/<contract>/proposals?<query..>")]
 async fn get_proposals(
-    order: Option<&str>,
-    limit: Option<i64>,
-    offset: Option<i64>,
-    filtered_account_id: Option<String>,
-    stage: Option<String>,
-    block_timestamp: Option<i64>, // support for feed update functionality
+    contract: &str,
+    query: ProposalQuery,
     db: &State<DB>,
-    // Json<PaginatedResponse<ProposalWithLatestSnapshotView>>
-) -> Option<Json<PaginatedResponse<ProposalWithLatestSnapshotView>>> {
-    // Get current timestamp
-    // let current_timestamp = chrono::Utc::now().timestamp();
-    let current_timestamp_nano = chrono::Utc::now().timestamp_nanos_opt().unwrap();
-    // Get last timestamp when database was updated
-    let last_updated_timestamp = db.get_last_updated_timestamp().await.unwrap();
-
-    println!("last_updated_timestamp: {:?}", last_updated_timestamp);
-    println!("current_timestamp: {:?}", current_timestamp_nano);
-
-    println!(
-        "Difference: {:?}",
-        current_timestamp_nano - last_updated_timestamp
-    );
-    println!(
-        "Duration: {:?}",
-        chrono::Duration::seconds(60).num_nanoseconds().unwrap()
-    );
-    // If we called nearblocks in the last 60 milliseconds return the database values
-    if current_timestamp_nano - last_updated_timestamp
-        < chrono::Duration::seconds(60).num_nanoseconds().unwrap()
-    {
-        let _proposals = db.get_proposals().await;
-        println!("Returning cached proposals");
-        return None;
-    }
-
-    println!("Fetching not yet indexed method calls from nearblocks");
-
-    let nearblocks_client = nearblocks_client::ApiClient::default();
-
-    // Nearblocks reacts with all contract changes since the timestamp we pass
-    // This could return 0 new tx in which case we get the database stuff anyway
-    // Or it could return 1 new tx in which case we want to update the database first
-    // then get it from database using the right queries
-    let nearblocks_unwrapped = match nearblocks_client
-        .get_account_txns_by_pagination(
-            "devhub.near".parse::<AccountId>().unwrap(),
-            // Instead of just set_block_height_callback we should get all method calls
-            // and handle them accordingly.
-            Some("set_block_height_callback".to_string()),
-            Some(timestamp_to_date_string(last_updated_timestamp)),
-            // if this limit hits 10 we might need to do it in a loop let's say there are 100 changes since the last call to nearblocks.
-            Some(25),
-            Some("asc".to_string()),
-        )
-        .await
-    {
-        Ok(nearblocks_unwrapped) => {
-            // If the response was successful, print the count of method calls
-            // println!("Response: {:?}", nearblocks_unwrapped);
-            nearblocks_unwrapped
-        }
-        Err(e) => {
-            // If there was an error, print it or handle it as needed
-            eprintln!("Failed to fetch data from nearblocks: {:?}", e);
-            nearblocks_client::ApiResponse { txns: vec![] }
+    config: &State<Config>,
+) -> Result<Json<PaginatedResponse<ProposalWithLatestSnapshotView>>> {
+    let contract = config.contract(contract)?;
+    // The background indexer keeps the database up to date, so we only read from it here
+    let order = query.order.as_deref().unwrap_or("desc");
+    let (limit, offset) = clamp_page(query.limit, query.offset);
+    let match_all_labels = match query.label_match.as_deref() {
+        None | Some("any") => false,
+        Some("all") => true,
+        Some(label_match) => {
+            return Err(Error::BadRequest(format!(
+                "label_match must be `any` or `all`, got `{}`",
+                label_match
+            )))
         }
     };
+    let stages = query
+        .stage
+        .iter()
+        .map(|stage| {
+            timeline_status_from_stage(stage)
+                .ok_or_else(|| Error::BadRequest(format!("Unknown stage `{}`", stage)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let (proposals, total) = db
+        .get_proposals_with_latest_snapshot(
+            contract.as_str(),
+            limit,
+            order,
+            offset,
+            query.filtered_account_id,
+            query.block_timestamp,
+            Some(stages),
+            Some(query.labels),
+            match_all_labels,
+            query.category,
+        )
+        .await?;
 
-    println!(
-        "Fetched {} method calls from nearblocks",
-        nearblocks_unwrapped.clone().txns.len()
-    );
-
-    let _ = process_transactions(&nearblocks_unwrapped.txns, db).await;
-
-    match nearblocks_unwrapped
-        .txns
-        // should we get the first or last?
-        .last()
-    {
-        Some(transaction) => {
-            println!("Added proposals to database, now adding timestamp.");
-
-            println!("Transaction timestamp: {}", transaction.block_timestamp);
-            let timestamp_nano: i64 = transaction.block_timestamp.parse().unwrap();
+    Ok(Json(PaginatedResponse::from_offset(
+        proposals, offset, limit, total,
+    )))
+}
 
-            println!("Parsed tx timestamp: {}", timestamp_nano);
-            db.set_last_updated_timestamp(timestamp_nano).await.unwrap();
+#[utoipa::path(
+    get,
+    path = "/{contract}/proposals/search?<q>&<limit>&<offset>",
+    responses(Error)
+)]
+#[get("/<contract>/proposals/search?<query..>")]
+async fn search_proposals(
+    contract: &str,
+    query: ProposalSearchQuery,
+    db: &State<DB>,
+    config: &State<Config>,
+) -> Result<Json<PaginatedResponse<ProposalSearchResult>>> {
+    let contract = config.contract(contract)?;
+    if query.q.trim().is_empty() {
+        return Err(Error::BadRequest("q must not be empty".to_string()));
+    }
+    let (limit, offset) = clamp_page(query.limit, query.offset);
 
-            println!("Added timestamp to database, returning proposals...");
-        }
-        None => {
-            println!("No transactions found")
-        }
-    };
+    let (results, total) = db
+        .search_proposals_with_latest_snapshot(contract.as_str(), &query.q, limit, offset)
+        .await?;
 
-    // TODO add back in
-    let order = order.unwrap_or("desc");
-    let limit = limit.unwrap_or(25);
-    let offset = offset.unwrap_or(0);
-    // let block_timestamp = block_timestamp.unwrap_or(None);
+    Ok(Json(PaginatedResponse::from_offset(
+        results, offset, limit, total,
+    )))
+}
 
-    let proposals = match db
-        .get_proposals_with_latest_snapshot(
+#[utoipa::path(
+    get,
+    path = "/{contract}/proposals/{proposal_id}/history?<order>&<limit>&<offset>&<from_ts>&<to_ts>&<editor_id>",
+    responses(Error)
+)]
+#[get("/<contract>/proposals/<proposal_id>/history?<query..>")]
+async fn get_proposal_history(
+    contract: &str,
+    proposal_id: i32,
+    query: ProposalHistoryQuery,
+    db: &State<DB>,
+    config: &State<Config>,
+) -> Result<Json<PaginatedResponse<ProposalSnapshotRecord>>> {
+    let contract = config.contract(contract)?;
+    let order = query.order.as_deref().unwrap_or("asc");
+    let (limit, offset) = clamp_page(query.limit, query.offset);
+
+    let (snapshots, total) = db
+        .get_proposal_snapshots(
+            contract.as_str(),
+            proposal_id,
             limit,
             order,
             offset,
-            filtered_account_id,
-            block_timestamp,
-            stage,
+            query.from_ts,
+            query.to_ts,
+            query.editor_id,
         )
-        .await
-    {
-        Err(e) => {
-            // race_of_sloths_server::error(
-            //     telegram,
-            //     &format!("Failed to get user contributions: {username}: {e}"),
-            // );
-            println!("Failed to get proposals: {:?}", e);
-            vec![]
-        }
-        Ok(proposals) => proposals,
-    };
+        .await?;
 
-    Some(Json(PaginatedResponse::new(
-        proposals.into_iter().map(Into::into).collect(),
-        1,
-        limit.try_into().unwrap(),
-        0, // TODO create a query that aggregates and counts the total
+    Ok(Json(PaginatedResponse::from_offset(
+        snapshots, offset, limit, total,
     )))
 }
 
-async fn process_transactions(transactions: &[Transaction], db: &State<DB>) -> Result<(), Status> {
+#[utoipa::path(
+    get,
+    path = "/{contract}/proposals/{proposal_id}/diff?<from_ts>&<to_ts>",
+    responses(Error)
+)]
+#[get("/<contract>/proposals/<proposal_id>/diff?<from_ts>&<to_ts>")]
+async fn get_proposal_diff(
+    contract: &str,
+    proposal_id: i32,
+    from_ts: Option<i64>,
+    to_ts: Option<i64>,
+    db: &State<DB>,
+    config: &State<Config>,
+) -> Result<Json<ProposalDiff>> {
+    let contract = config.contract(contract)?.as_str();
+    let not_found = |ts: Option<i64>| match ts {
+        Some(ts) => Error::NotFound(format!(
+            "Proposal {} has no snapshot at or before {}",
+            proposal_id, ts
+        )),
+        None => Error::NotFound(format!("Proposal {} has no snapshots", proposal_id)),
+    };
+
+    // Defaults to the latest snapshot
+    let to = db
+        .get_latest_proposal_snapshot(contract, proposal_id, to_ts)
+        .await?
+        .ok_or_else(|| not_found(to_ts))?;
+
+    let from = match from_ts {
+        Some(from_ts) => db
+            .get_latest_proposal_snapshot(contract, proposal_id, Some(from_ts))
+            .await?
+            .ok_or_else(|| not_found(Some(from_ts)))?,
+        // Defaults to the snapshot right before `to`, a proposal that was never edited has no changes
+        None => db
+            .get_latest_proposal_snapshot(contract, proposal_id, Some(to.ts - 1))
+            .await?
+            .unwrap_or_else(|| to.clone()),
+    };
+
+    Ok(Json(diff::diff_snapshots(&from, &to)))
+}
+
+// Called by the background indexer with the transactions fetched for every method
+// family, see `entrypoints::sync`
+pub async fn sync_proposals(
+    db: &DB,
+    sources: &Sources,
+    contract: &AccountId,
+    transactions: &[Transaction],
+) -> anyhow::Result<()> {
+    let contract_reader = sources.contract_reader(contract);
+    let contract = contract.as_str();
+    let transactions = unprocessed_txns(db, contract, PROPOSAL_METHOD_FAMILY, transactions).await?;
+
+    let contract_reader = contract_reader.as_ref();
+    process_and_advance(
+        db,
+        contract,
+        PROPOSAL_METHOD_FAMILY,
+        transactions,
+        |transaction| async move {
+            process_transactions(
+                std::slice::from_ref(&transaction),
+                db,
+                Some(contract_reader),
+            )
+            .await
+        },
+    )
+    .await
+}
+
+// Without an `contract_reader` the receipts are replayed offline from their args
+pub async fn process_transactions(
+    transactions: &[Transaction],
+    db: &DB,
+    contract_reader: Option<&dyn ContractReader>,
+) -> Result<()> {
     for transaction in transactions.iter() {
         if let Some(action) = transaction.actions.first() {
             let result = match action.method.as_str() {
+                // A failed receipt didn't change the proposal, we only keep it with the others
+                method
+                    if !transaction.receipt_outcome.status
+                        && PROPOSAL_METHODS.contains(&method) =>
+                {
+                    insert_receipt_dump(transaction, db).await
+                }
+                _ if !transaction.receipt_outcome.status => continue,
                 "set_block_height_callback" => {
                     handle_set_block_height_callback(transaction.to_owned(), db).await
                 }
-                "edit_proposal_versioned_timeline" => {
-                    handle_edit_proposal(transaction.to_owned(), db).await
-                }
-                "edit_proposal_timeline" => handle_edit_proposal(transaction.to_owned(), db).await,
-                "edit_proposal" => handle_edit_proposal(transaction.to_owned(), db).await,
-                "edit_proposal_linked_rfp" => {
-                    handle_edit_proposal(transaction.to_owned(), db).await
-                }
-                _ => {
-                    println!("Unhandled method: {}", action.method);
-                    continue;
+                "edit_proposal"
+                | "edit_proposal_timeline"
+                | "edit_proposal_versioned_timeline"
+                | "edit_proposal_linked_rfp" => {
+                    handle_edit_proposal(transaction.to_owned(), db, contract_reader).await
                 }
+                // The proposal is stored by its `set_block_height_callback`, which carries
+                // the id the contract assigned
+                "add_proposal" => insert_receipt_dump(transaction, db).await,
+                // RFP methods are handled by the RFP indexer
+                _ => continue,
             };
             result?;
         }
@@ -180,110 +262,209 @@ async fn process_transactions(transactions: &[Transaction], db: &State<DB>) -> R
     Ok(())
 }
 
-async fn handle_set_block_height_callback(
-    transaction: Transaction,
-    db: &State<DB>,
-) -> Result<(), Status> {
-    let action = transaction.clone().actions.first().unwrap().clone();
-    let json_args = action.args.clone();
+// Keeps a receipt that doesn't change a snapshot with the others
+async fn insert_receipt_dump(transaction: &Transaction, db: &DB) -> Result<()> {
+    // Only the edits name the proposal in their args
+    let id = transaction
+        .actions
+        .first()
+        .and_then(|action| action.decode_args::<PartialEditProposalArgs>().ok())
+        .map(|args| args.id);
+
+    let mut tx = db.begin().await?;
+    DB::insert_dump(&mut tx, &DumpRecord::from_transaction(transaction, id)?).await?;
+    tx.commit().await?;
+
+    Ok(())
+}
 
-    // println!("json_args: {:?}", json_args.clone());
-    let args: SetBlockHeightCallbackArgs = serde_json::from_str(&json_args).unwrap();
+async fn handle_set_block_height_callback(transaction: Transaction, db: &DB) -> Result<()> {
+    let action = transaction
+        .actions
+        .first()
+        .ok_or_else(|| Error::Decode("No actions found in transaction".to_string()))?;
+
+    let args: SetBlockHeightCallbackArgs = action.decode_args()?;
 
-    println!("Adding to the database... {}", args.clone().proposal.id);
-    let mut tx = db.begin().await.map_err(|_e| Status::InternalServerError)?;
+    println!("Adding to the database... {}", args.proposal.id);
+    let mut tx = db.begin().await?;
     DB::upsert_proposal(
         &mut tx,
-        args.clone().proposal.id,
-        args.clone().proposal.author_id.to_string(),
+        &transaction.receiver_account_id,
+        args.proposal.id,
+        args.proposal.author_id.to_string(),
     )
-    .await
-    .unwrap();
+    .await?;
 
-    let block_timestamp = transaction.clone().block_timestamp;
-    let block_height = transaction.clone().block.block_height;
+    let snapshot = ProposalSnapshotRecord::from_contract_proposal(
+        &transaction.receiver_account_id,
+        args.proposal,
+        transaction.block_timestamp.clone(),
+        transaction.block.block_height,
+    )?;
 
-    let snapshot: devhub_cache_api::db::types::ProposalSnapshotRecord =
-        FromContractProposal::from_contract_proposal(
-            args.proposal.clone(),
-            block_timestamp,
-            block_height,
-        );
+    DB::insert_proposal_snapshot(&mut tx, &snapshot).await?;
 
-    DB::insert_proposal_snapshot(&mut tx, &snapshot)
-        .await
-        .unwrap();
+    DB::insert_dump(
+        &mut tx,
+        &DumpRecord::from_transaction(&transaction, Some(snapshot.proposal_id))?,
+    )
+    .await?;
 
-    tx.commit()
-        .await
-        .map_err(|_e| Status::InternalServerError)?;
+    tx.commit().await?;
 
     Ok(())
 }
 
-fn get_proposal_id(transaction: &Transaction) -> Result<i32, &'static str> {
+async fn handle_edit_proposal(
+    transaction: Transaction,
+    db: &DB,
+    contract_reader: Option<&dyn ContractReader>,
+) -> Result<()> {
     let action = transaction
         .actions
         .first()
-        .ok_or("No actions found in transaction")?;
-
-    let args: PartialEditProposalArgs = serde_json::from_str(&action.args).map_err(|e| {
-        eprintln!("Failed to parse JSON: {:?}", e);
-        "Failed to parse proposal arguments"
-    })?;
-
-    Ok(args.id)
-}
-
-async fn handle_edit_proposal(
-    transaction: Transaction,
-    db: &State<DB>,
-) -> Result<(), rocket::http::Status> {
-    let rpc_service = RpcService::default();
-    let id = get_proposal_id(&transaction).map_err(|e| {
-        eprintln!("Failed to get proposal ID: {}", e);
-        Status::InternalServerError
-    })?;
-    let versioned_proposal = match rpc_service.get_proposal(id).await {
-        Ok(proposal) => proposal,
-        Err(e) => {
-            eprintln!("Failed to get proposal from RPC: {:?}", e);
-            return Err(Status::InternalServerError);
+        .ok_or_else(|| Error::Decode("No actions found in transaction".to_string()))?;
+    let contract = transaction.receiver_account_id.as_str();
+    let block_height = transaction.block.block_height;
+    let ts = transaction
+        .block_timestamp
+        .parse::<i64>()
+        .map_err(|e| Error::Decode(format!("Invalid block timestamp: {}", e)))?;
+    let PartialEditProposalArgs { id } = action.decode_args()?;
+
+    let snapshot = match (replay_edit(&transaction, db, id, ts).await, contract_reader) {
+        (Ok(snapshot), _) => snapshot,
+        // Without args we understand or a snapshot to apply them to, we read the
+        // proposal as it was at the block of this receipt
+        (Err(e @ (Error::Decode(_) | Error::NotFound(_))), Some(contract_reader)) => {
+            println!(
+                "Reading proposal {} at block {} instead: {}",
+                id, block_height, e
+            );
+            let proposal = contract_reader
+                .get_proposal_at(id, at_block_height(block_height))
+                .await?
+                .data;
+            ProposalSnapshotRecord::from_contract_proposal(
+                contract,
+                proposal.into(),
+                transaction.block_timestamp.clone(),
+                block_height,
+            )?
         }
+        (Err(e), _) => return Err(e),
     };
 
-    let mut tx = db.begin().await.map_err(|_e| Status::InternalServerError)?;
+    let mut tx = db.begin().await?;
 
-    let snapshot = ProposalSnapshotRecord::from_contract_proposal(
-        versioned_proposal.into(),
-        transaction.block_timestamp,
-        transaction.block.block_height,
-    );
+    DB::insert_proposal_snapshot(&mut tx, &snapshot).await?;
 
-    DB::insert_proposal_snapshot(&mut tx, &snapshot)
-        .await
-        .unwrap();
+    DB::insert_dump(
+        &mut tx,
+        &DumpRecord::from_transaction(&transaction, Some(id))?,
+    )
+    .await?;
 
-    tx.commit()
-        .await
-        .map_err(|_e| Status::InternalServerError)?;
+    tx.commit().await?;
 
     Ok(())
 }
 
-#[utoipa::path(get, path = "/proposals/{proposal_id}")]
-#[get("/<proposal_id>")]
-async fn get_proposal(proposal_id: i32) -> Result<Json<VersionedProposal>, rocket::http::Status> {
-    let rpc_service = RpcService::default();
-    // We should cache this in the future
-    // We should also add rate limiting to this endpoint
-    match rpc_service.get_proposal(proposal_id).await {
-        Ok(proposal) => Ok(Json(proposal)),
-        Err(e) => {
-            eprintln!("Failed to get proposal from RPC: {:?}", e);
-            Err(Status::InternalServerError)
+// Applies the edit in the args to the latest snapshot before this receipt
+async fn replay_edit(
+    transaction: &Transaction,
+    db: &DB,
+    id: i32,
+    ts: i64,
+) -> Result<ProposalSnapshotRecord> {
+    let action = transaction
+        .actions
+        .first()
+        .ok_or_else(|| Error::Decode("No actions found in transaction".to_string()))?;
+
+    let mut snapshot = db
+        .get_latest_proposal_snapshot(&transaction.receiver_account_id, id, Some(ts))
+        .await?
+        .ok_or_else(|| {
+            Error::NotFound(format!(
+                "No snapshot of proposal {} before block {}",
+                id, transaction.block.block_height
+            ))
+        })?;
+
+    match action.method.as_str() {
+        "edit_proposal" => {
+            let args: EditProposalArgs = action.decode_args()?;
+            snapshot.apply_body(&args.body, &args.labels);
+        }
+        "edit_proposal_timeline" => {
+            let args: EditProposalTimelineArgs = action.decode_args()?;
+            snapshot.apply_timeline(&args.timeline.into());
+        }
+        "edit_proposal_versioned_timeline" => {
+            let args: EditProposalVersionedTimelineArgs = action.decode_args()?;
+            snapshot.apply_timeline(&args.timeline);
         }
+        "edit_proposal_linked_rfp" => {
+            let args: EditProposalLinkedRfpArgs = action.decode_args()?;
+            snapshot.apply_linked_rfp(args.rfp_id);
+        }
+        method => {
+            return Err(Error::BadRequest(format!(
+                "{} is not a proposal edit",
+                method
+            )))
+        }
+    }
+
+    snapshot.block_height = transaction.block.block_height;
+    snapshot.ts = ts;
+    snapshot.editor_id = transaction.predecessor_account_id.clone();
+    snapshot.views = None;
+
+    Ok(snapshot)
+}
+
+#[utoipa::path(get, path = "/{contract}/proposals/{proposal_id}", responses(Error))]
+#[get("/<contract>/proposals/<proposal_id>", rank = 2)]
+async fn get_proposal(
+    contract: &str,
+    proposal_id: i32,
+    db: &State<DB>,
+    config: &State<Config>,
+    sources: &State<Sources>,
+) -> Result<Json<ProposalWithLatestSnapshotView>> {
+    let contract = config.contract(contract)?;
+
+    if let Some(proposal) = db
+        .get_proposal_with_latest_snapshot(contract.as_str(), proposal_id)
+        .await?
+    {
+        return Ok(Json(proposal));
     }
+
+    // Not indexed yet, so we ask the contract. We don't cache the answer: the contract
+    // doesn't say at which block the snapshot was made, the indexer stores it once it
+    // reaches the receipt.
+    // We should also add rate limiting to this endpoint
+    let contract_reader = sources.contract_reader(contract);
+    let result = contract_reader.get_proposal(proposal_id).await?;
+
+    let proposal: ContractProposal = result.data.into();
+    let author_id = proposal.author_id.to_string();
+    let snapshot = ProposalSnapshotRecord::from_contract_proposal(
+        contract.as_str(),
+        proposal.clone(),
+        proposal.snapshot.timestamp.to_string(),
+        result.block_height as i64,
+    )?;
+
+    let mut view = ProposalWithLatestSnapshotView::from_snapshot(author_id, snapshot);
+    // The block we read at, not the one of the snapshot
+    view.block_height = None;
+
+    Ok(Json(view))
 }
 
 pub fn stage() -> rocket::fairing::AdHoc {
@@ -291,6 +472,15 @@ pub fn stage() -> rocket::fairing::AdHoc {
     rocket::fairing::AdHoc::on_ignite("Proposal Stage", |rocket| async {
         println!("Proposal stage on ignite!");
 
-        rocket.mount("/proposals/", rocket::routes![get_proposals, get_proposal])
+        rocket.mount(
+            "/",
+            rocket::routes![
+                get_proposals,
+                get_proposal,
+                search_proposals,
+                get_proposal_history,
+                get_proposal_diff
+            ],
+        )
     })
 }
@@ -1,12 +1,19 @@
+use devhub_shared::proposal::timeline::{
+    TimelineStatusV1, TimelineStatusV2, VersionedTimelineStatus,
+};
 use devhub_shared::proposal::{
     Proposal, ProposalFundingCurrency, ProposalId, VersionedProposalBody,
 };
+use devhub_shared::rfp::RFPId;
 use near_sdk::near;
 use rocket::serde::{Deserialize, Serialize};
+use rocket::FromForm;
 use std::collections::HashSet;
 
 // Assuming these are the types you are working with
-use devhub_cache_api::db::types::ProposalSnapshotRecord;
+use crate::db::types::{DumpRecord, ProposalSnapshotRecord};
+use crate::error::{Error, Result};
+use crate::nearblocks_client::types::Transaction;
 // NOTE should this be VersionedProposal instead of Proposal?
 use devhub_shared::proposal::Proposal as ContractProposal;
 
@@ -22,7 +29,7 @@ pub trait ProposalBodyFields {
     fn get_requested_sponsor(&self) -> String;
     fn get_receiver_account(&self) -> String;
     fn get_supervisor(&self) -> Option<String>;
-    fn get_timeline(&self) -> String;
+    fn get_timeline(&self) -> serde_json::Value;
     fn get_linked_rfp(&self) -> &Option<u32>;
 }
 
@@ -137,16 +144,16 @@ impl ProposalBodyFields for VersionedProposalBody {
         }
     }
 
-    fn get_timeline(&self) -> String {
+    fn get_timeline(&self) -> serde_json::Value {
         match self {
             VersionedProposalBody::V0(body) => {
-                serde_json::to_string(&body.timeline).unwrap_or_default()
+                serde_json::to_value(&body.timeline).unwrap_or_default()
             }
             VersionedProposalBody::V1(body) => {
-                serde_json::to_string(&body.timeline).unwrap_or_default()
+                serde_json::to_value(&body.timeline).unwrap_or_default()
             }
             VersionedProposalBody::V2(body) => {
-                serde_json::to_string(&body.timeline).unwrap_or_default()
+                serde_json::to_value(&body.timeline).unwrap_or_default()
             }
         }
     }
@@ -154,63 +161,176 @@ impl ProposalBodyFields for VersionedProposalBody {
 }
 
 // Define a trait for the conversion
-pub trait FromContractProposal {
+pub trait FromContractProposal: Sized {
     fn from_contract_proposal(
+        contract: &str,
         proposal: ContractProposal,
         timestamp: String,
         block_height: i64,
-    ) -> Self;
+    ) -> Result<Self>;
 }
 
 impl FromContractProposal for ProposalSnapshotRecord {
     fn from_contract_proposal(
+        contract: &str,
         proposal: ContractProposal,
         timestamp: String,
         block_height: i64,
-    ) -> Self {
-        ProposalSnapshotRecord {
+    ) -> Result<Self> {
+        let ts = timestamp
+            .parse::<i64>()
+            .map_err(|e| Error::Decode(format!("Invalid timestamp {}: {}", timestamp, e)))?;
+        let mut snapshot = ProposalSnapshotRecord {
+            contract: contract.to_string(),
             proposal_id: proposal.id as i32,
             block_height,
-            ts: timestamp.parse::<i64>().unwrap_or_default(),
+            ts,
             editor_id: proposal.snapshot.editor_id.to_string(),
             social_db_post_block_height: proposal.social_db_post_block_height as i64,
-            labels: serde_json::Value::from(Vec::from_iter(
-                proposal.snapshot.labels.iter().cloned(),
-            )),
+            labels: serde_json::Value::Null,
             proposal_version: "V0".to_string(),
             proposal_body_version: "V2".to_string(),
-            name: Some(proposal.snapshot.body.get_name().clone()),
-            category: Some(proposal.snapshot.body.get_category().clone()),
-            summary: Some(proposal.snapshot.body.get_summary().clone()),
-            description: Some(proposal.snapshot.body.get_description().clone()),
-            linked_proposals: Some(serde_json::Value::from(Vec::from_iter(
-                proposal.snapshot.body.get_linked_proposals().to_vec(),
-            ))),
-            linked_rfp: proposal.snapshot.body.get_linked_rfp().map(|x| x as i32),
-            requested_sponsorship_usd_amount: Some(
-                *proposal
-                    .snapshot
-                    .body
-                    .get_requested_sponsorship_usd_amount() as i32,
-            ),
-            requested_sponsorship_paid_in_currency: Some(
-                proposal
-                    .snapshot
-                    .body
-                    .get_requested_sponsorship_paid_in_currency()
-                    .clone(),
-            ),
-            requested_sponsor: Some(proposal.snapshot.body.get_requested_sponsor().clone()),
-            receiver_account: Some(proposal.snapshot.body.get_receiver_account().clone()),
-            supervisor: proposal.snapshot.body.get_supervisor(),
-            timeline: Some(serde_json::Value::from(
-                proposal.snapshot.body.get_timeline().clone(),
-            )),
+            name: None,
+            category: None,
+            summary: None,
+            description: None,
+            linked_proposals: None,
+            linked_rfp: None,
+            requested_sponsorship_usd_amount: None,
+            requested_sponsorship_paid_in_currency: None,
+            requested_sponsor: None,
+            receiver_account: None,
+            supervisor: None,
+            timeline: None,
             views: None,
-        }
+        };
+        snapshot.apply_body(&proposal.snapshot.body, &proposal.snapshot.labels);
+        Ok(snapshot)
+    }
+}
+
+// Replays the edit methods of the contract on top of the previous snapshot,
+// so every snapshot holds the state at the block of its receipt
+pub trait ApplyProposalEdit {
+    fn apply_body(&mut self, body: &VersionedProposalBody, labels: &HashSet<String>);
+    fn apply_timeline(&mut self, timeline: &VersionedTimelineStatus);
+    fn apply_linked_rfp(&mut self, rfp_id: Option<RFPId>);
+}
+
+impl ApplyProposalEdit for ProposalSnapshotRecord {
+    fn apply_body(&mut self, body: &VersionedProposalBody, labels: &HashSet<String>) {
+        // The contract stores every body in its latest version
+        let body = VersionedProposalBody::V2(body.clone().latest_version());
+        // HashSets have no order, sort them so equal snapshots are stored the same way
+        let mut labels = Vec::from_iter(labels.iter().cloned());
+        labels.sort();
+
+        self.labels = serde_json::Value::from(labels);
+        self.proposal_body_version = "V2".to_string();
+        self.name = Some(body.get_name().clone());
+        self.category = Some(body.get_category().clone());
+        self.summary = Some(body.get_summary().clone());
+        self.description = Some(body.get_description().clone());
+        self.linked_proposals = Some(serde_json::Value::from(Vec::from_iter(
+            body.get_linked_proposals().to_vec(),
+        )));
+        self.linked_rfp = body.get_linked_rfp().map(|x| x as i32);
+        self.requested_sponsorship_usd_amount =
+            Some(*body.get_requested_sponsorship_usd_amount() as i32);
+        self.requested_sponsorship_paid_in_currency =
+            Some(body.get_requested_sponsorship_paid_in_currency());
+        self.requested_sponsor = Some(body.get_requested_sponsor());
+        self.receiver_account = Some(body.get_receiver_account());
+        self.supervisor = body.get_supervisor();
+        self.timeline = Some(body.get_timeline());
+    }
+
+    fn apply_timeline(&mut self, timeline: &VersionedTimelineStatus) {
+        self.timeline = Some(serde_json::to_value(timeline).unwrap_or_default());
+    }
+
+    fn apply_linked_rfp(&mut self, rfp_id: Option<RFPId>) {
+        self.linked_rfp = rfp_id.map(|x| x as i32);
+    }
+}
+
+// Query parameters of the proposal history entrypoint
+#[derive(Debug, FromForm)]
+pub struct ProposalQuery {
+    pub order: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+    pub filtered_account_id: Option<String>,
+    // support for feed update functionality
+    pub block_timestamp: Option<i64>,
+    // Repeatable, `?stage=REVIEW&stage=APPROVED` matches either
+    pub stage: Vec<String>,
+    // Repeatable, `?labels=a&labels=b`
+    pub labels: Vec<String>,
+    // `any` (default) or `all` of the labels
+    pub label_match: Option<String>,
+    pub category: Option<String>,
+}
+
+// Maps a `stage` query param to the `status` of the contract's TimelineStatus
+pub fn timeline_status_from_stage(stage: &str) -> Option<String> {
+    match stage.to_uppercase().as_str() {
+        "DRAFT" => Some("DRAFT".to_string()),
+        "REVIEW" => Some("REVIEW".to_string()),
+        "APPROVED" => Some("APPROVED".to_string()),
+        "REJECTED" => Some("REJECTED".to_string()),
+        "CANCELED" | "CANCELLED" => Some("CANCELLED".to_string()),
+        "APPROVED_CONDITIONALLY" => Some("APPROVED_CONDITIONALLY".to_string()),
+        "PAYMENT_PROCESSING" => Some("PAYMENT_PROCESSING".to_string()),
+        "FUNDED" => Some("FUNDED".to_string()),
+        _ => None,
+    }
+}
+
+#[derive(Debug, FromForm)]
+pub struct ProposalHistoryQuery {
+    pub order: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+    pub from_ts: Option<i64>,
+    pub to_ts: Option<i64>,
+    pub editor_id: Option<String>,
+}
+
+#[derive(Debug, FromForm)]
+pub struct ProposalSearchQuery {
+    pub q: String,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+// Define a trait for recording the receipt a snapshot was built from
+pub trait FromTransaction: Sized {
+    fn from_transaction(transaction: &Transaction, id: Option<i32>) -> Result<Self>;
+}
+
+impl FromTransaction for DumpRecord {
+    fn from_transaction(transaction: &Transaction, proposal_id: Option<i32>) -> Result<Self> {
+        let action = transaction.actions.first();
+        Ok(DumpRecord {
+            // Receipts are executed by the contract they were sent to
+            contract: transaction.receiver_account_id.clone(),
+            receipt_id: transaction.receipt_id.clone(),
+            method_name: action.map(|a| a.method.clone()).unwrap_or_default(),
+            block_height: transaction.block.block_height,
+            block_timestamp: transaction
+                .block_timestamp
+                .parse::<i64>()
+                .map_err(|e| Error::Decode(format!("Invalid block timestamp: {}", e)))?,
+            args: action.map(|a| a.args.clone()).unwrap_or_default(),
+            author: transaction.predecessor_account_id.clone(),
+            proposal_id,
+            succeeded: transaction.receipt_outcome.status,
+        })
     }
 }
 
+#[allow(dead_code)]
 #[derive(Serialize, Deserialize)]
 pub struct AddProposalArgs {
     body: VersionedProposalBody,
@@ -225,14 +345,161 @@ pub struct SetBlockHeightCallbackArgs {
 
 #[near(serializers=[borsh, json])]
 #[derive(Clone)]
-// NOTE: deserializing didn't work for some reason so instead we use get_proposal from RPC
 pub struct EditProposalArgs {
     pub id: ProposalId,
     pub body: VersionedProposalBody,
     pub labels: HashSet<String>,
 }
 
+// `edit_proposal_timeline` predates the versioned timeline and took a V1 status,
+// later deployments accept the V2 status there too
+#[derive(Deserialize, Clone)]
+#[serde(untagged)]
+pub enum TimelineArg {
+    V2(TimelineStatusV2),
+    V1(TimelineStatusV1),
+}
+
+impl From<TimelineArg> for VersionedTimelineStatus {
+    fn from(timeline: TimelineArg) -> Self {
+        match timeline {
+            TimelineArg::V2(v2) => VersionedTimelineStatus::V1(v2),
+            TimelineArg::V1(v1) => VersionedTimelineStatus::V1(v1.into()),
+        }
+    }
+}
+
+// The `id` of the timeline and linked RFP edits is read with `PartialEditProposalArgs`
+#[derive(Deserialize, Clone)]
+pub struct EditProposalTimelineArgs {
+    pub timeline: TimelineArg,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct EditProposalVersionedTimelineArgs {
+    pub timeline: VersionedTimelineStatus,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct EditProposalLinkedRfpArgs {
+    pub rfp_id: Option<RFPId>,
+}
+
 #[derive(Deserialize, Clone)]
 pub struct PartialEditProposalArgs {
     pub id: i32,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nearblocks_client::types::Action;
+    use serde_json::json;
+
+    fn snapshot() -> ProposalSnapshotRecord {
+        serde_json::from_value(json!({
+            "contract": "devhub.near",
+            "proposal_id": 1,
+            "block_height": 1,
+            "ts": 1,
+            "editor_id": "alice.near",
+            "social_db_post_block_height": 1,
+            "labels": [],
+            "proposal_version": "V0",
+            "proposal_body_version": "V0",
+        }))
+        .unwrap()
+    }
+
+    fn body(version: &str) -> serde_json::Value {
+        json!({
+            "proposal_body_version": version,
+            "name": "Proposal",
+            "category": "Marketing",
+            "summary": "summary",
+            "description": "description",
+            "linked_proposals": [2],
+            "requested_sponsorship_usd_amount": "100",
+            "requested_sponsorship_paid_in_currency": "USDC",
+            "receiver_account": "alice.near",
+            "requested_sponsor": "neardevdao.near",
+            "supervisor": null,
+            "timeline": { "status": "DRAFT" },
+        })
+    }
+
+    fn apply_edit(args: serde_json::Value) -> ProposalSnapshotRecord {
+        let action = Action {
+            action: "FUNCTION_CALL".to_string(),
+            method: "edit_proposal".to_string(),
+            deposit: 0,
+            fee: 0.0,
+            args: args.to_string(),
+        };
+        let args: EditProposalArgs = action.decode_args().unwrap();
+        let mut snapshot = snapshot();
+        snapshot.apply_body(&args.body, &args.labels);
+        snapshot
+    }
+
+    #[test]
+    fn stores_labels_sorted() {
+        let snapshot =
+            apply_edit(json!({ "id": 1, "body": body("V0"), "labels": ["c", "a", "d", "b"] }));
+
+        assert_eq!(snapshot.labels, json!(["a", "b", "c", "d"]));
+    }
+
+    #[test]
+    fn upgrades_v0_body_to_v2() {
+        let snapshot = apply_edit(json!({ "id": 1, "body": body("V0"), "labels": [] }));
+
+        assert_eq!(snapshot.proposal_body_version, "V2");
+        assert_eq!(snapshot.linked_proposals, Some(json!([2])));
+        assert_eq!(snapshot.linked_rfp, None);
+        assert_eq!(snapshot.requested_sponsorship_usd_amount, Some(100));
+        assert_eq!(
+            snapshot.timeline,
+            Some(json!({ "timeline_version": "V1", "status": "DRAFT" }))
+        );
+    }
+
+    #[test]
+    fn upgrades_v1_body_to_v2() {
+        let mut body = body("V1");
+        body["linked_rfp"] = json!(3);
+        let snapshot = apply_edit(json!({ "id": 1, "body": body, "labels": [] }));
+
+        assert_eq!(snapshot.proposal_body_version, "V2");
+        assert_eq!(snapshot.linked_rfp, Some(3));
+        assert_eq!(
+            snapshot.timeline,
+            Some(json!({ "timeline_version": "V1", "status": "DRAFT" }))
+        );
+    }
+
+    #[test]
+    fn rejects_an_invalid_timestamp() {
+        let mut snapshot = body("V1");
+        snapshot["editor_id"] = json!("alice.near");
+        snapshot["timestamp"] = json!("1");
+        snapshot["labels"] = json!([]);
+        let proposal: ContractProposal = serde_json::from_value(json!({
+            "id": 1,
+            "author_id": "alice.near",
+            "social_db_post_block_height": "1",
+            "snapshot": snapshot,
+            "snapshot_history": [],
+        }))
+        .unwrap();
+
+        let result = ProposalSnapshotRecord::from_contract_proposal(
+            "devhub.near",
+            proposal,
+            "not a timestamp".to_string(),
+            1,
+        );
+
+        assert!(matches!(result, Err(Error::Decode(_))));
+    }
+}
@@ -0,0 +1,343 @@
+use super::proposal::types::FromTransaction;
+use crate::api_background_service::{process_and_advance, unprocessed_txns};
+use crate::config::Config;
+use crate::db::types::{RfpDumpRecord, RfpSnapshotRecord, RfpWithLatestSnapshotView};
+use crate::db::DB;
+use crate::error::{Error, Result};
+use crate::nearblocks_client::types::Transaction;
+use crate::sources::{at_block_height, ContractReader, Sources};
+use crate::types::{clamp_page, PaginatedResponse};
+use devhub_shared::rfp::{TimelineStatus, RFP};
+use near_account_id::AccountId;
+use rocket::{get, serde::json::Json, FromForm, State};
+
+pub mod types;
+use self::types::*;
+
+// Key of the RFP methods in the `sync_cursors` table
+pub const RFP_METHOD_FAMILY: &str = "rfp";
+
+// Every receipt calling one of these is stored in `rfp_dumps`, failed ones included
+const RFP_METHODS: &[&str] = &[
+    "add_rfp",
+    "set_rfp_block_height_callback",
+    "edit_rfp",
+    "edit_rfp_timeline",
+    "edit_rfp_internal",
+    "cancel_rfp",
+];
+
+// Struct for query parameters
+#[derive(Debug, FromForm)]
+struct RfpQuery {
+    order: Option<String>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+    filtered_account_id: Option<String>,
+    block_timestamp: Option<i64>,
+    // Repeatable, `?stage=EVALUATION&stage=PROPOSAL_SELECTED` matches either
+    stage: Vec<String>,
+}
+
+impl FromTransaction for RfpDumpRecord {
+    fn from_transaction(transaction: &Transaction, rfp_id: Option<i32>) -> Result<Self> {
+        let action = transaction.actions.first();
+        Ok(RfpDumpRecord {
+            contract: transaction.receiver_account_id.clone(),
+            receipt_id: transaction.receipt_id.clone(),
+            method_name: action.map(|a| a.method.clone()).unwrap_or_default(),
+            block_height: transaction.block.block_height,
+            block_timestamp: transaction
+                .block_timestamp
+                .parse::<i64>()
+                .map_err(|e| Error::Decode(format!("Invalid block timestamp: {}", e)))?,
+            args: action.map(|a| a.args.clone()).unwrap_or_default(),
+            author: transaction.predecessor_account_id.clone(),
+            rfp_id,
+            succeeded: transaction.receipt_outcome.status,
+        })
+    }
+}
+
+fn get_rfp_id(transaction: &Transaction) -> Result<i32> {
+    let action = transaction
+        .actions
+        .first()
+        .ok_or_else(|| Error::Decode("No actions found in transaction".to_string()))?;
+
+    let PartialEditRFPArgs { id } = action.decode_args()?;
+
+    Ok(id)
+}
+
+#[utoipa::path(
+    get,
+    path = "/{contract}/rfps?<order>&<limit>&<offset>&<filtered_account_id>&<block_timestamp>&<stage>",
+    responses(Error)
+)]
+#[get("/<contract>/rfps?<query..>")]
+async fn get_rfps(
+    contract: &str,
+    query: RfpQuery,
+    db: &State<DB>,
+    config: &State<Config>,
+) -> Result<Json<PaginatedResponse<RfpWithLatestSnapshotView>>> {
+    let contract = config.contract(contract)?;
+    let order = query.order.as_deref().unwrap_or("desc");
+    let (limit, offset) = clamp_page(query.limit, query.offset);
+    let stages = query
+        .stage
+        .iter()
+        .map(|stage| {
+            timeline_status_from_stage(stage)
+                .ok_or_else(|| Error::BadRequest(format!("Unknown stage `{}`", stage)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let (rfps, total) = db
+        .get_rfps_with_latest_snapshot(
+            contract.as_str(),
+            limit,
+            order,
+            offset,
+            query.filtered_account_id,
+            query.block_timestamp,
+            Some(stages),
+        )
+        .await?;
+
+    Ok(Json(PaginatedResponse::from_offset(
+        rfps, offset, limit, total,
+    )))
+}
+
+// Called by the background indexer, see `entrypoints::sync`
+pub async fn sync_rfps(
+    db: &DB,
+    sources: &Sources,
+    contract: &AccountId,
+    transactions: &[Transaction],
+) -> anyhow::Result<()> {
+    let contract_reader = sources.contract_reader(contract);
+    let contract = contract.as_str();
+    let transactions = unprocessed_txns(db, contract, RFP_METHOD_FAMILY, transactions).await?;
+
+    let contract_reader = contract_reader.as_ref();
+    process_and_advance(
+        db,
+        contract,
+        RFP_METHOD_FAMILY,
+        transactions,
+        |transaction| async move {
+            process_transactions(
+                std::slice::from_ref(&transaction),
+                db,
+                Some(contract_reader),
+            )
+            .await
+        },
+    )
+    .await
+}
+
+// Without an `contract_reader` the receipts are replayed offline from their args
+pub async fn process_transactions(
+    transactions: &[Transaction],
+    db: &DB,
+    contract_reader: Option<&dyn ContractReader>,
+) -> Result<()> {
+    for transaction in transactions.iter() {
+        if let Some(action) = transaction.actions.first() {
+            let result = match action.method.as_str() {
+                // A failed receipt didn't change the RFP, we only keep it with the others
+                method if !transaction.receipt_outcome.status && RFP_METHODS.contains(&method) => {
+                    insert_receipt_dump(transaction, db).await
+                }
+                _ if !transaction.receipt_outcome.status => continue,
+                "set_rfp_block_height_callback" => {
+                    handle_set_rfp_block_height_callback(transaction.to_owned(), db).await
+                }
+                "edit_rfp" | "edit_rfp_timeline" | "edit_rfp_internal" | "cancel_rfp" => {
+                    handle_edit_rfp(transaction.to_owned(), db, contract_reader).await
+                }
+                // The RFP is stored by its `set_rfp_block_height_callback`, which carries the
+                // id the contract assigned
+                "add_rfp" => insert_receipt_dump(transaction, db).await,
+                // Proposal methods are handled by the proposal indexer
+                _ => continue,
+            };
+            result?;
+        }
+    }
+
+    Ok(())
+}
+
+// Keeps a receipt that doesn't change a snapshot with the others
+async fn insert_receipt_dump(transaction: &Transaction, db: &DB) -> Result<()> {
+    // Only the edits name the RFP in their args
+    let id = get_rfp_id(transaction).ok();
+
+    let mut tx = db.begin().await?;
+    DB::insert_rfp_dump(&mut tx, &RfpDumpRecord::from_transaction(transaction, id)?).await?;
+    tx.commit().await?;
+
+    Ok(())
+}
+
+async fn handle_set_rfp_block_height_callback(transaction: Transaction, db: &DB) -> Result<()> {
+    let action = transaction
+        .actions
+        .first()
+        .ok_or_else(|| Error::Decode("No actions found in transaction".to_string()))?;
+
+    let args: SetRfpBlockHeightCallbackArgs = action.decode_args()?;
+
+    println!("Adding rfp to the database... {}", args.rfp.id);
+    let mut tx = db.begin().await?;
+
+    DB::upsert_rfp(
+        &mut tx,
+        &transaction.receiver_account_id,
+        args.rfp.id,
+        args.rfp.author_id.to_string(),
+    )
+    .await?;
+
+    let snapshot = RfpSnapshotRecord::from_contract_rfp(
+        &transaction.receiver_account_id,
+        args.rfp.clone(),
+        transaction.block_timestamp.clone(),
+    )?;
+
+    DB::upsert_rfp_snapshot(&mut tx, &snapshot).await?;
+
+    DB::insert_rfp_dump(
+        &mut tx,
+        &RfpDumpRecord::from_transaction(&transaction, Some(args.rfp.id as i32))?,
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+async fn handle_edit_rfp(
+    transaction: Transaction,
+    db: &DB,
+    contract_reader: Option<&dyn ContractReader>,
+) -> Result<()> {
+    let id = get_rfp_id(&transaction)?;
+
+    let (snapshot, author_id) = match contract_reader {
+        // The RFP as it was right after this receipt, not as it is today. Unlike the args
+        // this includes the proposals linked since the previous edit.
+        Some(contract_reader) => {
+            let rfp: RFP = contract_reader
+                .get_rfp_at(id, at_block_height(transaction.block.block_height))
+                .await?
+                .into();
+            let author_id = rfp.author_id.to_string();
+
+            let snapshot = RfpSnapshotRecord::from_contract_rfp(
+                &transaction.receiver_account_id,
+                rfp,
+                transaction.block_timestamp.clone(),
+            )?;
+            (snapshot, Some(author_id))
+        }
+        None => (replay_edit(&transaction, db, id).await?, None),
+    };
+
+    let mut tx = db.begin().await?;
+
+    // A replayed edit builds on a previous snapshot, so its RFP is stored already
+    if let Some(author_id) = author_id {
+        DB::upsert_rfp(
+            &mut tx,
+            &transaction.receiver_account_id,
+            id as u32,
+            author_id,
+        )
+        .await?;
+    }
+
+    DB::upsert_rfp_snapshot(&mut tx, &snapshot).await?;
+
+    DB::insert_rfp_dump(
+        &mut tx,
+        &RfpDumpRecord::from_transaction(&transaction, Some(id))?,
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+// Applies the edit in the args to the latest snapshot before this receipt
+async fn replay_edit(transaction: &Transaction, db: &DB, id: i32) -> Result<RfpSnapshotRecord> {
+    let action = transaction
+        .actions
+        .first()
+        .ok_or_else(|| Error::Decode("No actions found in transaction".to_string()))?;
+    let ts = transaction
+        .block_timestamp
+        .parse::<i64>()
+        .map_err(|e| Error::Decode(format!("Invalid block timestamp: {}", e)))?;
+
+    let mut snapshot = db
+        .get_latest_rfp_snapshot(&transaction.receiver_account_id, id, Some(ts))
+        .await?
+        .ok_or_else(|| {
+            Error::NotFound(format!(
+                "No snapshot of RFP {} before block {}",
+                id, transaction.block.block_height
+            ))
+        })?;
+
+    match action.method.as_str() {
+        "edit_rfp" | "edit_rfp_internal" => {
+            let args: EditRfpArgs = action.decode_args()?;
+            snapshot.apply_body(&args.body, &args.labels);
+        }
+        "edit_rfp_timeline" => {
+            let args: EditRfpTimelineArgs = action.decode_args()?;
+            snapshot.apply_timeline(&args.timeline);
+        }
+        "cancel_rfp" => snapshot.apply_timeline(&TimelineStatus::Cancelled),
+        method => return Err(Error::BadRequest(format!("{} is not an RFP edit", method))),
+    }
+
+    snapshot.block_height = transaction.block.block_height;
+    snapshot.ts = ts;
+    snapshot.editor_id = transaction.predecessor_account_id.clone();
+    snapshot.views = None;
+
+    Ok(snapshot)
+}
+
+#[utoipa::path(get, path = "/{contract}/rfps/{rfp_id}", responses(Error))]
+#[get("/<contract>/rfps/<rfp_id>", rank = 2)]
+async fn get_rfp(
+    contract: &str,
+    rfp_id: i32,
+    db: &State<DB>,
+    config: &State<Config>,
+) -> Result<Json<RfpWithLatestSnapshotView>> {
+    let contract = config.contract(contract)?;
+
+    db.get_rfp_with_latest_snapshot(contract.as_str(), rfp_id)
+        .await?
+        .map(Json)
+        .ok_or_else(|| Error::NotFound(format!("RFP {} not found", rfp_id)))
+}
+
+pub fn stage() -> rocket::fairing::AdHoc {
+    rocket::fairing::AdHoc::on_ignite("Rfp Stage", |rocket| async {
+        println!("Rfp stage on ignite!");
+
+        rocket.mount("/", rocket::routes![get_rfps, get_rfp])
+    })
+}
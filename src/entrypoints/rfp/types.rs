@@ -0,0 +1,156 @@
+use devhub_shared::rfp::{TimelineStatus, VersionedRFPBody, RFP};
+use rocket::serde::Deserialize;
+use std::collections::HashSet;
+
+use crate::db::types::RfpSnapshotRecord;
+use crate::error::{Error, Result};
+// NOTE should this be VersionedRFP instead of RFP?
+use devhub_shared::rfp::RFP as ContractRfp;
+
+// Define a trait for accessing various fields
+pub trait RfpBodyFields {
+    fn get_name(&self) -> &String;
+    fn get_summary(&self) -> &String;
+    fn get_description(&self) -> &String;
+    fn get_timeline(&self) -> serde_json::Value;
+    fn get_submission_deadline(&self) -> u64;
+    fn get_rfp_body_version(&self) -> String;
+}
+
+// Implement the trait for VersionedRFPBody
+impl RfpBodyFields for VersionedRFPBody {
+    fn get_name(&self) -> &String {
+        match self {
+            VersionedRFPBody::V0(body) => &body.name,
+        }
+    }
+
+    fn get_summary(&self) -> &String {
+        match self {
+            VersionedRFPBody::V0(body) => &body.summary,
+        }
+    }
+
+    fn get_description(&self) -> &String {
+        match self {
+            VersionedRFPBody::V0(body) => &body.description,
+        }
+    }
+
+    fn get_timeline(&self) -> serde_json::Value {
+        match self {
+            VersionedRFPBody::V0(body) => serde_json::to_value(&body.timeline).unwrap_or_default(),
+        }
+    }
+
+    fn get_submission_deadline(&self) -> u64 {
+        match self {
+            VersionedRFPBody::V0(body) => body.submission_deadline,
+        }
+    }
+
+    fn get_rfp_body_version(&self) -> String {
+        match self {
+            VersionedRFPBody::V0(_) => "V0".to_string(),
+        }
+    }
+}
+
+// Define a trait for the conversion
+pub trait FromContractRfp: Sized {
+    fn from_contract_rfp(contract: &str, rfp: ContractRfp, timestamp: String) -> Result<Self>;
+}
+
+impl FromContractRfp for RfpSnapshotRecord {
+    fn from_contract_rfp(contract: &str, rfp: ContractRfp, timestamp: String) -> Result<Self> {
+        let ts = timestamp
+            .parse::<i64>()
+            .map_err(|e| Error::Decode(format!("Invalid timestamp {}: {}", timestamp, e)))?;
+        // HashSets have no order, sort them so equal snapshots are stored the same way
+        let mut labels = Vec::from_iter(rfp.snapshot.labels.iter().cloned());
+        labels.sort();
+        let mut linked_proposals = Vec::from_iter(rfp.snapshot.linked_proposals.iter().cloned());
+        linked_proposals.sort();
+
+        Ok(RfpSnapshotRecord {
+            contract: contract.to_string(),
+            rfp_id: rfp.id as i32,
+            // The contract keeps the block of the edit with every snapshot
+            block_height: rfp.snapshot.block_height as i64,
+            ts,
+            editor_id: rfp.snapshot.editor_id.to_string(),
+            social_db_post_block_height: rfp.social_db_post_block_height as i64,
+            labels: serde_json::Value::from(labels),
+            linked_proposals: Some(serde_json::Value::from(linked_proposals)),
+            rfp_version: "V0".to_string(),
+            rfp_body_version: rfp.snapshot.body.get_rfp_body_version(),
+            name: Some(rfp.snapshot.body.get_name().clone()),
+            // RFPs don't have a category
+            category: None,
+            summary: Some(rfp.snapshot.body.get_summary().clone()),
+            description: Some(rfp.snapshot.body.get_description().clone()),
+            timeline: Some(rfp.snapshot.body.get_timeline()),
+            submission_deadline: rfp.snapshot.body.get_submission_deadline() as i64,
+            views: None,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+pub struct PartialEditRFPArgs {
+    pub id: i32,
+}
+
+#[derive(Deserialize)]
+pub struct SetRfpBlockHeightCallbackArgs {
+    pub rfp: RFP,
+}
+
+// The `id` of the edits is read with `PartialEditRFPArgs`
+#[derive(Deserialize)]
+pub struct EditRfpArgs {
+    pub body: VersionedRFPBody,
+    pub labels: HashSet<String>,
+}
+
+#[derive(Deserialize)]
+pub struct EditRfpTimelineArgs {
+    pub timeline: TimelineStatus,
+}
+
+// Replays the edit methods of the contract on top of the previous snapshot. Linked
+// proposals change through proposal edits, so those are carried over as they were.
+pub trait ApplyRfpEdit {
+    fn apply_body(&mut self, body: &VersionedRFPBody, labels: &HashSet<String>);
+    fn apply_timeline(&mut self, timeline: &TimelineStatus);
+}
+
+impl ApplyRfpEdit for RfpSnapshotRecord {
+    fn apply_body(&mut self, body: &VersionedRFPBody, labels: &HashSet<String>) {
+        let mut labels = Vec::from_iter(labels.iter().cloned());
+        labels.sort();
+
+        self.labels = serde_json::Value::from(labels);
+        self.rfp_body_version = body.get_rfp_body_version();
+        self.name = Some(body.get_name().clone());
+        self.summary = Some(body.get_summary().clone());
+        self.description = Some(body.get_description().clone());
+        self.timeline = Some(body.get_timeline());
+        self.submission_deadline = body.get_submission_deadline() as i64;
+    }
+
+    fn apply_timeline(&mut self, timeline: &TimelineStatus) {
+        self.timeline = Some(serde_json::to_value(timeline).unwrap_or_default());
+    }
+}
+
+// Maps a `stage` query param to the `status` of the RFP's TimelineStatus
+pub fn timeline_status_from_stage(stage: &str) -> Option<String> {
+    match stage.to_uppercase().as_str() {
+        "ACCEPTING_SUBMISSIONS" => Some("ACCEPTING_SUBMISSIONS".to_string()),
+        "EVALUATION" => Some("EVALUATION".to_string()),
+        "PROPOSAL_SELECTED" => Some("PROPOSAL_SELECTED".to_string()),
+        "CANCELED" | "CANCELLED" => Some("CANCELLED".to_string()),
+        _ => None,
+    }
+}
@@ -0,0 +1,230 @@
+use rocket::fairing::AdHoc;
+use rocket::http::{ContentType, Header, Status};
+use rocket::response::{self, Responder, Response};
+use rocket::Request;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::Cursor;
+use utoipa::openapi::{ContentBuilder, Ref, RefOr, ResponseBuilder};
+use utoipa::{IntoResponses, ToSchema};
+
+const REQUEST_ID_HEADER: &str = "X-Request-Id";
+const PROBLEM_CONTENT_TYPE: (&str, &str) = ("application", "problem+json");
+
+#[derive(Debug)]
+pub enum Error {
+    // A view call to the contract failed
+    Rpc(String),
+    // The contract panicked during a view call
+    Contract(String),
+    Nearblocks(reqwest::Error),
+    Db(anyhow::Error),
+    // Contract or nearblocks data we couldn't make sense of
+    Decode(String),
+    NotFound(String),
+    BadRequest(String),
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+impl Error {
+    pub fn status(&self) -> Status {
+        match self {
+            Error::Rpc(_) | Error::Contract(_) | Error::Nearblocks(_) => Status::BadGateway,
+            Error::Db(_) | Error::Decode(_) => Status::InternalServerError,
+            Error::NotFound(_) => Status::NotFound,
+            Error::BadRequest(_) => Status::BadRequest,
+        }
+    }
+
+    // Whether trying again later can't help, e.g. a receipt whose arguments we can't decode
+    // or a view call the contract panics on. Failing RPC nodes, nearblocks and most database
+    // errors are usually temporary, but data we can't store (SQLSTATE class 22) or that
+    // breaks a constraint (class 23) fails the same way every time.
+    pub fn is_permanent(&self) -> bool {
+        match self {
+            Error::Contract(_) | Error::Decode(_) | Error::NotFound(_) | Error::BadRequest(_) => {
+                true
+            }
+            Error::Db(e) => e
+                .downcast_ref::<sqlx::Error>()
+                .and_then(|e| e.as_database_error())
+                .and_then(|e| e.code())
+                .is_some_and(|code| code.starts_with("22") || code.starts_with("23")),
+            Error::Rpc(_) | Error::Nearblocks(_) => false,
+        }
+    }
+
+    // Database errors can contain queries and connection details, those only go to the logs
+    fn detail(&self) -> Option<String> {
+        match self {
+            Error::Db(_) => None,
+            Error::Nearblocks(e) => Some(e.to_string()),
+            Error::Rpc(e)
+            | Error::Contract(e)
+            | Error::Decode(e)
+            | Error::NotFound(e)
+            | Error::BadRequest(e) => Some(e.clone()),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Rpc(e) => write!(f, "RPC error: {}", e),
+            Error::Contract(e) => write!(f, "Contract error: {}", e),
+            Error::Nearblocks(e) => write!(f, "Nearblocks error: {}", e),
+            Error::Db(e) => write!(f, "Database error: {:#}", e),
+            Error::Decode(e) => write!(f, "Failed to decode: {}", e),
+            Error::NotFound(e) => write!(f, "Not found: {}", e),
+            Error::BadRequest(e) => write!(f, "Bad request: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Nearblocks(e) => Some(e),
+            Error::Db(e) => Some(e.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<anyhow::Error> for Error {
+    fn from(e: anyhow::Error) -> Self {
+        Error::Db(e)
+    }
+}
+
+impl From<sqlx::Error> for Error {
+    fn from(e: sqlx::Error) -> Self {
+        Error::Db(e.into())
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        Error::Nearblocks(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Decode(e.to_string())
+    }
+}
+
+impl<'r> Responder<'r, 'static> for Error {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        eprintln!("[{}] {}", RequestId::of(request), self);
+        Problem::new(self.status(), self.detail(), request).respond_to(request)
+    }
+}
+
+// Documents the problem responses of a path, use `responses(Error)` in `#[utoipa::path]`
+impl IntoResponses for Error {
+    fn responses() -> BTreeMap<String, RefOr<utoipa::openapi::response::Response>> {
+        [
+            (Status::BadRequest, "The request parameters are invalid"),
+            (Status::NotFound, "The resource doesn't exist"),
+            (Status::InternalServerError, "The cache failed"),
+            (Status::BadGateway, "The RPC or nearblocks request failed"),
+        ]
+        .into_iter()
+        .map(|(status, description)| {
+            let response = ResponseBuilder::new()
+                .description(description)
+                .content(
+                    format!("{}/{}", PROBLEM_CONTENT_TYPE.0, PROBLEM_CONTENT_TYPE.1),
+                    ContentBuilder::new()
+                        .schema(Ref::from_schema_name("Problem"))
+                        .build(),
+                )
+                .build();
+            (status.code.to_string(), response.into())
+        })
+        .collect()
+    }
+}
+
+// RFC 7807 problem details
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Problem {
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    pub title: String,
+    pub status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    pub instance: String,
+    // Same as the `X-Request-Id` response header, to find the request in the logs
+    pub request_id: String,
+}
+
+impl Problem {
+    pub fn new(status: Status, detail: Option<String>, request: &Request<'_>) -> Self {
+        Self {
+            problem_type: "about:blank".to_string(),
+            title: status.reason_lossy().to_string(),
+            status: status.code,
+            detail,
+            instance: request.uri().path().to_string(),
+            request_id: RequestId::of(request).to_string(),
+        }
+    }
+
+    // For the catchers, which only know the status
+    pub fn from_status(status: Status, request: &Request<'_>) -> Self {
+        Self::new(status, None, request)
+    }
+}
+
+impl<'r> Responder<'r, 'static> for Problem {
+    fn respond_to(self, _: &'r Request<'_>) -> response::Result<'static> {
+        let body = serde_json::to_string(&self).map_err(|_| Status::InternalServerError)?;
+        Response::build()
+            .status(Status::new(self.status))
+            .header(ContentType::new(
+                PROBLEM_CONTENT_TYPE.0,
+                PROBLEM_CONTENT_TYPE.1,
+            ))
+            .sized_body(body.len(), Cursor::new(body))
+            .ok()
+    }
+}
+
+// Taken from the `X-Request-Id` request header when a proxy set one, generated otherwise
+pub struct RequestId(String);
+
+impl RequestId {
+    pub fn of<'r>(request: &'r Request<'_>) -> &'r RequestId {
+        request.local_cache(|| {
+            let id = request
+                .headers()
+                .get_one(REQUEST_ID_HEADER)
+                .map(|id| id.to_string())
+                .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+            RequestId(id)
+        })
+    }
+}
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+// Echoes the request id on every response
+pub fn stage() -> AdHoc {
+    AdHoc::on_response("Request id", |request, response| {
+        Box::pin(async move {
+            let id = RequestId::of(request).to_string();
+            response.set_header(Header::new(REQUEST_ID_HEADER, id));
+        })
+    })
+}
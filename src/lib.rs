@@ -1,8 +1,13 @@
 pub mod api_background_service;
 pub mod api_client;
+pub mod community;
+pub mod config;
 pub mod db;
+pub mod entrypoints;
+pub mod error;
 pub mod nearblocks_client;
 pub mod rpc_service;
+pub mod sources;
 pub mod types;
 use chrono::DateTime;
 
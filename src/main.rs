@@ -1,12 +1,24 @@
-use rocket::{catch, catchers, get, launch, routes};
+use rocket::http::Status;
+use rocket::{catch, catchers, get, routes, Build, Request, Rocket};
+use rocket_db_pools::Database;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
-mod entrypoints;
-use devhub_cache_api::db;
-use entrypoints::ApiDoc;
+mod backfill;
+mod replay;
+use devhub_cache_api::api_background_service::ApiBackgroundService;
+use devhub_cache_api::config::{self, Config};
+use devhub_cache_api::db::{self, DB};
+use devhub_cache_api::entrypoints::{self, ApiDoc};
+use devhub_cache_api::error::{self, Problem};
+use devhub_cache_api::sources::{self, Sources};
 use rocket_cors::AllowedOrigins;
 
+// How often the background indexer polls nearblocks for new contract calls
+const INDEXER_INTERVAL: Duration = Duration::from_secs(60);
+
 #[get("/")]
 fn index() -> &'static str {
     "Welcome from fly.io!!!!!"
@@ -19,29 +31,35 @@ fn robots() -> &'static str {
 }
 
 #[catch(422)]
-fn unprocessable_entity() -> &'static str {
-    "Custom 422 Error: Unprocessable Entity"
+fn unprocessable_entity(request: &Request) -> Problem {
+    Problem::from_status(Status::UnprocessableEntity, request)
 }
 
 #[catch(500)]
-fn internal_server_error() -> &'static str {
-    "Custom 500 Error: Internal Server Error"
+fn internal_server_error(request: &Request) -> Problem {
+    Problem::from_status(Status::InternalServerError, request)
 }
 
 #[catch(404)]
-fn not_found() -> &'static str {
-    "Custom 404 Error: Not Found"
+fn not_found(request: &Request) -> Problem {
+    Problem::from_status(Status::NotFound, request)
 }
 
 #[catch(400)]
-fn bad_request() -> &'static str {
-    "Custom 400 Error: Bad Request"
+fn bad_request(request: &Request) -> Problem {
+    Problem::from_status(Status::BadRequest, request)
 }
 
-#[launch]
-fn rocket() -> _ {
-    dotenvy::dotenv().ok();
+#[catch(default)]
+fn default_catcher(status: Status, request: &Request) -> Problem {
+    Problem::from_status(status, request)
+}
+
+fn rocket() -> Rocket<Build> {
     let atomic_bool = Arc::new(std::sync::atomic::AtomicBool::new(true));
+    let indexer: Arc<Mutex<Option<ApiBackgroundService>>> = Arc::new(Mutex::new(None));
+    let indexer_on_shutdown = indexer.clone();
+    let running = atomic_bool.clone();
 
     let allowed_origins = AllowedOrigins::some_exact(&[
         "http://localhost:3000",
@@ -56,14 +74,45 @@ fn rocket() -> _ {
 
     rocket::build()
         .attach(cors)
+        .attach(config::stage())
+        .attach(sources::stage())
+        .attach(error::stage())
         .attach(db::stage())
         .mount("/", routes![robots, index])
         .attach(entrypoints::stage())
+        .attach(rocket::fairing::AdHoc::on_liftoff(
+            "Start indexing proposals, rfps and communities from nearblocks",
+            move |rocket| {
+                Box::pin(async move {
+                    let db = DB::fetch(rocket)
+                        .expect("Database should be initialized before liftoff")
+                        .clone();
+                    let config = rocket
+                        .state::<Config>()
+                        .expect("Config should be loaded before liftoff")
+                        .clone();
+                    let sources = rocket
+                        .state::<Sources>()
+                        .expect("Sources should be set up before liftoff")
+                        .clone();
+                    let mut service = ApiBackgroundService::new(db, INDEXER_INTERVAL);
+                    service.start(running, move |db| {
+                        let config = config.clone();
+                        let sources = sources.clone();
+                        async move { entrypoints::sync(&db, &config, &sources).await }
+                    });
+                    *indexer.lock().await = Some(service);
+                })
+            },
+        ))
         .attach(rocket::fairing::AdHoc::on_shutdown(
-            "Stop loading users from Near and Github metadata",
+            "Stop indexing proposals and rfps from nearblocks",
             |_| {
                 Box::pin(async move {
                     atomic_bool.store(false, std::sync::atomic::Ordering::Relaxed);
+                    if let Some(service) = indexer_on_shutdown.lock().await.take() {
+                        service.shutdown().await;
+                    }
                 })
             },
         ))
@@ -77,7 +126,59 @@ fn rocket() -> _ {
                 unprocessable_entity,
                 internal_server_error,
                 not_found,
-                bad_request
+                bad_request,
+                default_catcher
             ],
         )
 }
+
+// Loads the config and runs the migrations without serving any routes
+async fn ignite_for_command() -> anyhow::Result<(DB, Config, Sources)> {
+    let rocket = rocket::build()
+        .attach(config::stage())
+        .attach(sources::stage())
+        .attach(db::stage())
+        .ignite()
+        .await?;
+    let db = DB::fetch(&rocket)
+        .expect("Database should be initialized after ignite")
+        .clone();
+    let config = rocket
+        .state::<Config>()
+        .expect("Config should be loaded after ignite")
+        .clone();
+    let sources = rocket
+        .state::<Sources>()
+        .expect("Sources should be set up after ignite")
+        .clone();
+
+    Ok((db, config, sources))
+}
+
+// `devhub-cache-api` serves the API, `backfill` seeds the cache from the contracts and
+// `replay` rebuilds the snapshots from the stored receipts
+#[rocket::main]
+async fn main() -> anyhow::Result<()> {
+    dotenvy::dotenv().ok();
+
+    let command = std::env::args().nth(1);
+    match command.as_deref() {
+        None | Some("serve") => {
+            rocket().launch().await?;
+        }
+        Some("backfill") => {
+            let (db, config, sources) = ignite_for_command().await?;
+            backfill::run(&db, &config, &sources).await?;
+        }
+        Some("replay") => {
+            let (db, config, _) = ignite_for_command().await?;
+            replay::run(&db, &config).await?;
+        }
+        Some(command) => anyhow::bail!(
+            "Unknown command {}, expected serve, backfill or replay",
+            command
+        ),
+    }
+
+    Ok(())
+}
@@ -1,14 +1,24 @@
+use crate::sources::TransactionSource;
+use crate::timestamp_to_date_string;
+use futures::stream::{self, Stream};
+use futures::TryStreamExt;
 use near_sdk::AccountId;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 
 pub mod types;
-use types::Transaction;
+use types::{PageItem, Transaction};
+
+// Nearblocks caps `per_page` at 25 for the public API
+const MAX_PER_PAGE: i32 = 25;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ApiResponse {
-    // Define the response fields
-    pub txns: Vec<Transaction>,
+    // Decoded one by one with `Transaction::decode_item`
+    pub txns: Vec<serde_json::Value>,
+    // Id of the last transaction in `txns`, pass it back to get the next page
+    #[serde(default)]
+    pub cursor: Option<String>,
 }
 
 #[derive(Clone)]
@@ -17,8 +27,7 @@ pub struct ApiClient {
     client: Client,
 }
 
-// TODO Create a nearblocks client
-// https://api.nearblocks.io/v1/account/devhub.near/txns?method=add_proposal&after_date=2024-10-10&page=1&per_page=25&order=desc
+// https://api.nearblocks.io/v1/account/devhub.near/txns?method=add_proposal&after_date=2024-10-10&cursor=123&per_page=25&order=asc
 
 impl Default for ApiClient {
     fn default() -> Self {
@@ -34,22 +43,38 @@ impl ApiClient {
         Self::default()
     }
 
+    // `base_url` is joined with the endpoint, so it needs the trailing slash
+    pub fn with_base_url(base_url: &str) -> Self {
+        let base_url = if base_url.ends_with('/') {
+            base_url.to_string()
+        } else {
+            format!("{}/", base_url)
+        };
+        Self {
+            base_url,
+            client: Client::new(),
+        }
+    }
+
     pub async fn get_account_txns_by_pagination(
         &self,
         account_id: AccountId,
         method: Option<String>,
         since_date: Option<String>,
+        cursor: Option<String>,
         limit: Option<i32>,
         order: Option<String>,
     ) -> Result<ApiResponse, reqwest::Error> {
-        // TODO page = 1
-        let query_params = format!(
-            "?method={}&after_date={}&page=1&per_page={}&order={}",
+        let mut query_params = format!(
+            "?method={}&after_date={}&per_page={}&order={}",
             method.unwrap_or_default(),
             since_date.unwrap_or("2024-10-10".to_string()),
-            limit.unwrap_or(10),
+            limit.unwrap_or(10).min(MAX_PER_PAGE),
             order.unwrap_or("desc".to_string())
         );
+        if let Some(cursor) = cursor {
+            query_params.push_str(&format!("&cursor={}", cursor));
+        }
         let endpoint = format!("v1/account/{}/txns", account_id);
         let url = self.base_url.clone() + &endpoint + &query_params;
         let res = self
@@ -61,4 +86,205 @@ impl ApiClient {
             .await?;
         Ok(res)
     }
+
+    // Follows nearblocks' cursor until every transaction since `since_date` has been
+    // yielded, oldest block first. `cursor` is the `id` of the last transaction that was
+    // already processed, so passing it back after a restart resumes right after it.
+    pub fn stream_account_txns(
+        &self,
+        account_id: AccountId,
+        method: Option<String>,
+        since_date: Option<String>,
+        cursor: Option<String>,
+    ) -> impl Stream<Item = Result<PageItem, reqwest::Error>> + '_ {
+        struct State {
+            buffer: std::vec::IntoIter<serde_json::Value>,
+            cursor: Option<String>,
+            drained: bool,
+        }
+
+        let initial = State {
+            buffer: Vec::new().into_iter(),
+            cursor,
+            drained: false,
+        };
+
+        stream::try_unfold(initial, move |mut state| {
+            let account_id = account_id.clone();
+            let method = method.clone();
+            let since_date = since_date.clone();
+            async move {
+                loop {
+                    if let Some(raw) = state.buffer.next() {
+                        return Ok(Some((Transaction::decode_item(raw), state)));
+                    }
+                    if state.drained {
+                        return Ok(None);
+                    }
+
+                    let page = self
+                        .get_account_txns_by_pagination(
+                            account_id.clone(),
+                            method.clone(),
+                            since_date.clone(),
+                            state.cursor.clone(),
+                            Some(MAX_PER_PAGE),
+                            Some("asc".to_string()),
+                        )
+                        .await?;
+
+                    let mut txns = page.txns;
+                    // A short page means there is nothing left to fetch after this one
+                    state.drained = txns.len() < MAX_PER_PAGE as usize;
+                    state.cursor = page.cursor.or_else(|| {
+                        txns.last()
+                            .and_then(|tx| tx["id"].as_str())
+                            .map(String::from)
+                    });
+                    if state.cursor.is_none() {
+                        state.drained = true;
+                    }
+
+                    txns.sort_by_key(|tx| tx["block"]["block_height"].as_i64());
+                    state.buffer = txns.into_iter();
+                }
+            }
+        })
+    }
+}
+
+#[rocket::async_trait]
+impl TransactionSource for ApiClient {
+    async fn account_txns(
+        &self,
+        contract: &AccountId,
+        method: Option<String>,
+        since: Option<i64>,
+        cursor: Option<String>,
+    ) -> anyhow::Result<Vec<PageItem>> {
+        // Nearblocks only filters by date, so without a cursor this starts at the day of `since`
+        let transactions = self
+            .stream_account_txns(
+                contract.clone(),
+                method,
+                since.map(timestamp_to_date_string),
+                cursor,
+            )
+            .try_collect()
+            .await?;
+
+        Ok(transactions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    fn txn(id: usize) -> Value {
+        json!({
+            "id": id.to_string(),
+            "receipt_id": format!("receipt{}", id),
+            "predecessor_account_id": "alice.near",
+            "receiver_account_id": "devhub.near",
+            "receipt_kind": "ACTION",
+            "receipt_block": { "block_hash": "hash", "block_height": id, "block_timestamp": id },
+            "receipt_outcome": {
+                "gas_burnt": 0,
+                "tokens_burnt": 0,
+                "executor_account_id": "devhub.near",
+                "status": true
+            },
+            "transaction_hash": "hash",
+            "included_in_block_hash": "hash",
+            "block_timestamp": id.to_string(),
+            "block": { "block_height": id },
+            "receipt_conversion_tokens_burnt": "0",
+            "actions": [],
+            "actions_agg": { "deposit": 0 },
+            "outcomes": { "status": true },
+            "outcomes_agg": { "transaction_fee": 0 }
+        })
+    }
+
+    // Serves `total` transactions with ids from 1 in pages of `MAX_PER_PAGE`, starting
+    // after the `cursor` of the request, and records the cursor of every request
+    async fn serve(total: usize) -> (ApiClient, Arc<Mutex<Vec<Option<String>>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let cursors = Arc::new(Mutex::new(vec![]));
+
+        let requested = cursors.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = vec![0; 4096];
+                let read = socket.read(&mut request).await.unwrap();
+                let request = String::from_utf8_lossy(&request[..read]).to_string();
+                let path = request.split_whitespace().nth(1).unwrap().to_string();
+                let url = url::Url::parse(&format!("http://localhost{}", path)).unwrap();
+                let cursor = url
+                    .query_pairs()
+                    .find(|(key, _)| key == "cursor")
+                    .map(|(_, value)| value.to_string());
+                requested.lock().unwrap().push(cursor.clone());
+
+                let start = cursor.map(|c| c.parse::<usize>().unwrap()).unwrap_or(0);
+                let end = (start + MAX_PER_PAGE as usize).min(total);
+                let txns: Vec<Value> = (start + 1..=end).map(txn).collect();
+                let body = json!({ "txns": txns }).to_string();
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        (ApiClient::with_base_url(&base_url), cursors)
+    }
+
+    async fn ids(client: &ApiClient, cursor: Option<String>) -> Vec<String> {
+        let items: Vec<PageItem> = client
+            .stream_account_txns("devhub.near".parse().unwrap(), None, None, cursor)
+            .try_collect()
+            .await
+            .unwrap();
+        items.into_iter().map(|item| item.unwrap().id).collect()
+    }
+
+    #[tokio::test]
+    async fn follows_the_cursor_until_a_short_page() {
+        let (client, cursors) = serve(30).await;
+
+        let ids = ids(&client, None).await;
+
+        assert_eq!(ids, (1..=30).map(|id| id.to_string()).collect::<Vec<_>>());
+        assert_eq!(*cursors.lock().unwrap(), vec![None, Some("25".to_string())]);
+    }
+
+    #[tokio::test]
+    async fn stops_at_an_empty_page() {
+        let (client, cursors) = serve(25).await;
+
+        let ids = ids(&client, None).await;
+
+        assert_eq!(ids.len(), 25);
+        assert_eq!(*cursors.lock().unwrap(), vec![None, Some("25".to_string())]);
+    }
+
+    #[tokio::test]
+    async fn resumes_after_the_given_cursor() {
+        let (client, cursors) = serve(30).await;
+
+        let ids = ids(&client, Some("27".to_string())).await;
+
+        assert_eq!(ids, vec!["28", "29", "30"]);
+        assert_eq!(*cursors.lock().unwrap(), vec![Some("27".to_string())]);
+    }
 }
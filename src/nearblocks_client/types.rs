@@ -1,3 +1,6 @@
+use crate::error::{Error, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -20,6 +23,98 @@ pub struct Transaction {
     pub outcomes_agg: OutcomesAgg,
 }
 
+impl Transaction {
+    // A function call receipt with only the fields we store in the dumps, the fees and
+    // hashes nearblocks adds aren't needed to process it again
+    #[allow(clippy::too_many_arguments)]
+    pub fn from_stored_receipt(
+        receipt_id: &str,
+        contract: &str,
+        predecessor_account_id: &str,
+        method: &str,
+        args: &str,
+        block_height: i64,
+        block_timestamp: i64,
+        succeeded: bool,
+    ) -> Self {
+        Transaction {
+            id: receipt_id.to_string(),
+            receipt_id: receipt_id.to_string(),
+            predecessor_account_id: predecessor_account_id.to_string(),
+            receiver_account_id: contract.to_string(),
+            receipt_kind: "ACTION".to_string(),
+            receipt_block: Block {
+                block_hash: String::new(),
+                block_height,
+                block_timestamp,
+            },
+            receipt_outcome: ReceiptOutcome {
+                gas_burnt: 0.0,
+                tokens_burnt: 0.0,
+                executor_account_id: contract.to_string(),
+                status: succeeded,
+            },
+            transaction_hash: String::new(),
+            included_in_block_hash: String::new(),
+            block_timestamp: block_timestamp.to_string(),
+            block: BlockInfo { block_height },
+            receipt_conversion_tokens_burnt: "0".to_string(),
+            actions: vec![Action {
+                action: "FUNCTION_CALL".to_string(),
+                method: method.to_string(),
+                deposit: 0,
+                fee: 0.0,
+                args: args.to_string(),
+            }],
+            actions_agg: ActionsAgg { deposit: 0 },
+            outcomes: Outcomes { status: succeeded },
+            outcomes_agg: OutcomesAgg {
+                transaction_fee: 0.0,
+            },
+        }
+    }
+}
+
+// A transaction of a nearblocks page is decoded on its own, so one malformed transaction
+// doesn't fail the whole page
+pub type PageItem = std::result::Result<Transaction, MalformedTransaction>;
+
+impl Transaction {
+    pub fn decode_item(raw: serde_json::Value) -> PageItem {
+        serde_json::from_value(raw.clone()).map_err(|e| MalformedTransaction {
+            raw,
+            error: format!("Malformed nearblocks transaction: {}", e),
+        })
+    }
+}
+
+// Kept as nearblocks returned it, so it can be quarantined and retried once we can decode it
+#[derive(Debug, Clone)]
+pub struct MalformedTransaction {
+    pub raw: serde_json::Value,
+    pub error: String,
+}
+
+impl MalformedTransaction {
+    pub fn receipt_id(&self) -> Option<&str> {
+        self.raw["receipt_id"].as_str()
+    }
+
+    pub fn method(&self) -> Option<&str> {
+        self.raw["actions"][0]["method"].as_str()
+    }
+
+    pub fn block_height(&self) -> Option<i64> {
+        self.raw["block"]["block_height"].as_i64()
+    }
+
+    pub fn block_timestamp(&self) -> Option<i64> {
+        self.raw["block_timestamp"]
+            .as_str()
+            .and_then(|ts| ts.parse().ok())
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Block {
     pub block_hash: String,
@@ -49,6 +144,33 @@ pub struct Action {
     pub args: String,
 }
 
+impl Action {
+    // Nearblocks returns the args as JSON when they are valid UTF-8 JSON and as base64
+    // otherwise, sometimes the JSON itself comes wrapped in a string
+    pub fn decode_args<T: DeserializeOwned>(&self) -> Result<T> {
+        let value = match serde_json::from_str::<serde_json::Value>(&self.args) {
+            Ok(serde_json::Value::String(inner)) => decode_args_str(&inner)?,
+            Ok(value) => value,
+            Err(_) => decode_args_str(&self.args)?,
+        };
+
+        serde_json::from_value(value)
+            .map_err(|e| Error::Decode(format!("Failed to parse {} args: {}", self.method, e)))
+    }
+}
+
+fn decode_args_str(args: &str) -> Result<serde_json::Value> {
+    if let Ok(value) = serde_json::from_str(args) {
+        return Ok(value);
+    }
+
+    let bytes = STANDARD
+        .decode(args.trim())
+        .map_err(|e| Error::Decode(format!("Args are neither JSON nor base64: {}", e)))?;
+    serde_json::from_slice(&bytes)
+        .map_err(|e| Error::Decode(format!("Base64 args are not JSON: {}", e)))
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ActionsAgg {
     pub deposit: i64,
@@ -63,3 +185,70 @@ pub struct Outcomes {
 pub struct OutcomesAgg {
     pub transaction_fee: f64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn action(args: &str) -> Action {
+        Action {
+            action: "FUNCTION_CALL".to_string(),
+            method: "edit_proposal".to_string(),
+            deposit: 0,
+            fee: 0.0,
+            args: args.to_string(),
+        }
+    }
+
+    #[test]
+    fn decodes_json_args() {
+        let args: Value = action(r#"{"id":1}"#).decode_args().unwrap();
+
+        assert_eq!(args, json!({ "id": 1 }));
+    }
+
+    #[test]
+    fn decodes_base64_args() {
+        let args: Value = action(&STANDARD.encode(r#"{"id":1}"#))
+            .decode_args()
+            .unwrap();
+
+        assert_eq!(args, json!({ "id": 1 }));
+    }
+
+    #[test]
+    fn decodes_json_wrapped_in_a_string() {
+        let args: Value = action(r#""{\"id\":1}""#).decode_args().unwrap();
+
+        assert_eq!(args, json!({ "id": 1 }));
+    }
+
+    #[test]
+    fn decodes_base64_wrapped_in_a_string() {
+        let wrapped = json!(STANDARD.encode(r#"{"id":1}"#)).to_string();
+        let args: Value = action(&wrapped).decode_args().unwrap();
+
+        assert_eq!(args, json!({ "id": 1 }));
+    }
+
+    #[test]
+    fn fails_to_decode_garbage() {
+        let result = action("not args!").decode_args::<Value>();
+
+        assert!(matches!(result, Err(Error::Decode(_))));
+    }
+
+    #[test]
+    fn fails_to_decode_args_of_another_shape() {
+        #[derive(Debug, Deserialize)]
+        struct Args {
+            #[allow(dead_code)]
+            id: u32,
+        }
+
+        let result = action(r#"{"name":"x"}"#).decode_args::<Args>();
+
+        assert!(matches!(result, Err(Error::Decode(_))));
+    }
+}
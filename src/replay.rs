@@ -0,0 +1,107 @@
+use devhub_cache_api::api_background_service::record_failed_receipt;
+use devhub_cache_api::config::Config;
+use devhub_cache_api::db::DB;
+use devhub_cache_api::entrypoints::{proposal, rfp};
+use devhub_cache_api::nearblocks_client::types::Transaction;
+
+// Rebuilds the proposal and RFP snapshots of the configured contracts from the stored
+// receipts, without nearblocks or RPC. Receipts that can't be replayed offline end up in
+// `failed_receipts`, where the admin retry reads them from the contract.
+//
+// The receipts are replayed under a staging contract and swapped in at the end in one
+// transaction, so an interrupted replay leaves the cached snapshots as they were.
+// Snapshots that didn't come from a receipt, e.g. from `backfill`, are dropped, running
+// `backfill` afterwards seeds those proposals and RFPs again.
+pub async fn run(db: &DB, config: &Config) -> anyhow::Result<()> {
+    for contract in config.contracts.iter() {
+        let contract = contract.as_str();
+        let staging = staging_contract(contract);
+        let dumps = db.get_all_dumps(contract).await?;
+        let rfp_dumps = db.get_all_rfp_dumps(contract).await?;
+
+        // Leftovers of an interrupted replay
+        let mut tx = db.begin().await?;
+        DB::delete_proposal_snapshots(&mut tx, &staging).await?;
+        DB::delete_rfp_snapshots(&mut tx, &staging).await?;
+        tx.commit().await?;
+
+        let transactions: Vec<Transaction> = dumps.iter().map(Transaction::from).collect();
+        let failed = replay_family(
+            db,
+            contract,
+            proposal::PROPOSAL_METHOD_FAMILY,
+            &transactions,
+        )
+        .await?;
+        println!(
+            "Replayed {} proposal receipts of {}, {} failed",
+            transactions.len(),
+            contract,
+            failed
+        );
+
+        let transactions: Vec<Transaction> = rfp_dumps.iter().map(Transaction::from).collect();
+        let failed = replay_family(db, contract, rfp::RFP_METHOD_FAMILY, &transactions).await?;
+        println!(
+            "Replayed {} rfp receipts of {}, {} failed",
+            transactions.len(),
+            contract,
+            failed
+        );
+
+        let mut tx = db.begin().await?;
+        let proposal_snapshots = DB::swap_proposal_snapshots(&mut tx, &staging, contract).await?;
+        let rfp_snapshots = DB::swap_rfp_snapshots(&mut tx, &staging, contract).await?;
+        tx.commit().await?;
+
+        println!(
+            "Replaced the snapshots of {} with {} proposal and {} rfp snapshots",
+            contract, proposal_snapshots, rfp_snapshots
+        );
+    }
+
+    Ok(())
+}
+
+// `#` can't appear in an account id, so the staged rows never mix with a real contract
+fn staging_contract(contract: &str) -> String {
+    format!("{}#replay", contract)
+}
+
+// Returns the number of receipts that couldn't be replayed
+async fn replay_family(
+    db: &DB,
+    contract: &str,
+    method_family: &str,
+    transactions: &[Transaction],
+) -> anyhow::Result<usize> {
+    let mut failed = 0;
+
+    for transaction in transactions.iter() {
+        // The handlers store the snapshots under the contract of the receipt
+        let mut staged = transaction.clone();
+        staged.receiver_account_id = staging_contract(contract);
+        let transactions = std::slice::from_ref(&staged);
+        let result = match method_family {
+            proposal::PROPOSAL_METHOD_FAMILY => {
+                proposal::process_transactions(transactions, db, None).await
+            }
+            _ => rfp::process_transactions(transactions, db, None).await,
+        };
+
+        match result {
+            Ok(()) => {}
+            Err(e) if e.is_permanent() => {
+                eprintln!(
+                    "Could not replay receipt {} of {}: {}",
+                    transaction.receipt_id, method_family, e
+                );
+                record_failed_receipt(db, contract, method_family, transaction, &e).await?;
+                failed += 1;
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    Ok(failed)
+}
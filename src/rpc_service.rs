@@ -1,10 +1,14 @@
+use crate::community::{AddOn, Community};
+use crate::error::{Error, Result};
+use crate::sources::{not_found_on_panic, ContractReader};
 use devhub_shared::proposal::VersionedProposal;
 use devhub_shared::rfp::VersionedRFP;
 use near_account_id::AccountId;
+use near_api::errors::QueryError;
 use near_api::{types::Data, Contract, NetworkConfig};
-use rocket::http::Status;
+use near_jsonrpc_client::methods::query::{RpcQueryError, RpcQueryRequest};
+use near_primitives::types::BlockReference;
 use rocket::serde::json::json;
-use rocket::FromForm;
 use serde::Deserialize;
 
 #[derive(Deserialize)]
@@ -18,109 +22,157 @@ pub struct RpcService {
     contract: Contract,
 }
 
-#[derive(FromForm)]
-pub struct ProposalParams {
-    proposal_ids: Option<Vec<i32>>,
-}
-
-impl Default for RpcService {
-    fn default() -> Self {
-        Self {
-            network: NetworkConfig::mainnet(),
-            contract: Contract("devhub.near".parse::<AccountId>().unwrap()),
-        }
-    }
-}
-
 /**
  * Usage
  * use devhub_cache_api::rpc_service::RpcService;
- * let rpc_service = RpcService::new(Some("devhub.near".parse::<AccountId>().unwrap()));
- * let proposals = rpc_service.get_proposals().await;
+ * let rpc_service = RpcService::new(NetworkConfig::mainnet(), "devhub.near".parse::<AccountId>().unwrap());
+ * or for any configured contract from `Sources` in Rocket state
+ * let contract_reader = sources.contract_reader(&contract);
+ * let proposal = contract_reader.get_proposal(1).await;
  */
-
 impl RpcService {
-    pub fn new(account_id: Option<AccountId>) -> Self {
-        match account_id {
-            Some(id) => Self {
-                network: NetworkConfig::mainnet(),
-                contract: Contract(id),
-            },
-            None => Self::default(),
+    pub fn new(network: NetworkConfig, account_id: AccountId) -> Self {
+        Self {
+            network,
+            contract: Contract(account_id),
         }
     }
+}
+
+// Tells a panicking contract or a result we can't decode, which won't change when asked
+// again, apart from a failing node or connection
+fn query_error(e: QueryError<RpcQueryRequest>) -> Error {
+    match &e {
+        QueryError::JsonRpcError(rpc_error) => match rpc_error.handler_error() {
+            Some(RpcQueryError::ContractExecutionError { vm_error, .. }) => {
+                Error::Contract(vm_error.clone())
+            }
+            _ => Error::Rpc(e.to_string()),
+        },
+        QueryError::DeserializeError(_) => Error::Decode(e.to_string()),
+        _ => Error::Rpc(e.to_string()),
+    }
+}
 
-    pub async fn get_proposal(&self, proposal_id: i32) -> Result<VersionedProposal, String> {
+#[rocket::async_trait]
+impl ContractReader for RpcService {
+    async fn get_proposal_at(
+        &self,
+        proposal_id: i32,
+        block: BlockReference,
+    ) -> Result<Data<VersionedProposal>> {
         let result: Result<Data<VersionedProposal>, _> = self
             .contract
             .call_function("get_proposal", json!({ "proposal_id": proposal_id }))
-            .unwrap()
+            .map_err(|e| Error::Rpc(e.to_string()))?
             .read_only()
+            .at(block)
             .fetch_from(&self.network)
             .await;
 
-        match result {
-            Ok(proposal) => Ok(proposal.data),
-            Err(e) => Err(e.to_string()),
-        }
+        result.map_err(|e| not_found_on_panic(query_error(e), "Proposal", proposal_id))
     }
 
-    pub async fn get_rfp(&self, rfp_id: i32) -> Result<VersionedRFP, String> {
+    async fn get_rfp_at(&self, rfp_id: i32, block: BlockReference) -> Result<VersionedRFP> {
         let result: Result<Data<VersionedRFP>, _> = self
             .contract
             .call_function("get_rfp", json!({ "rfp_id": rfp_id }))
-            .unwrap()
+            .map_err(|e| Error::Rpc(e.to_string()))?
             .read_only()
+            .at(block)
             .fetch_from(&self.network)
             .await;
 
-        match result {
-            Ok(proposal) => Ok(proposal.data),
-            Err(e) => Err(e.to_string()),
-        }
+        result
+            .map(|res| res.data)
+            .map_err(|e| not_found_on_panic(query_error(e), "RFP", rfp_id))
     }
 
-    // TODO return value should it be Result or Option?
-    pub async fn get_proposals(&self) -> Result<Vec<VersionedProposal>, String> {
-        // TODO: Add query params , params: ProposalParams
-        let params: ProposalParams = ProposalParams {
-            proposal_ids: Some(vec![200, 199]),
-        };
-
-        let mut args = json!({});
-        if let Some(proposal_ids) = params.proposal_ids {
-            println!("Proposal ids: {:?}", proposal_ids);
-            args = json!({ "ids": proposal_ids });
-        }
+    async fn get_community_at(
+        &self,
+        handle: &str,
+        block: BlockReference,
+    ) -> Result<Option<Community>> {
+        let result: Result<Data<Option<Community>>, _> = self
+            .contract
+            .call_function("get_community", json!({ "handle": handle }))
+            .map_err(|e| Error::Rpc(e.to_string()))?
+            .read_only()
+            .at(block)
+            .fetch_from(&self.network)
+            .await;
+
+        result.map(|res| res.data).map_err(query_error)
+    }
 
-        match self
+    async fn get_addon_at(&self, addon_id: &str, block: BlockReference) -> Result<Option<AddOn>> {
+        let result: Result<Data<Option<AddOn>>, _> = self
             .contract
-            .call_function("get_proposals", args)
-            .unwrap()
-            .read_only::<Vec<VersionedProposal>>()
+            .call_function("get_addon", json!({ "id": addon_id }))
+            .map_err(|e| Error::Rpc(e.to_string()))?
+            .read_only()
+            .at(block)
             .fetch_from(&self.network)
-            .await
-        {
-            Ok(res) => Ok(res.data),
-            Err(e) => Err(e.to_string()),
-        }
+            .await;
+
+        result.map(|res| res.data).map_err(query_error)
     }
 
-    pub async fn get_all_proposal_ids(&self) -> Result<Vec<i32>, Status> {
+    async fn get_proposals(
+        &self,
+        proposal_ids: &[i32],
+        block: BlockReference,
+    ) -> Result<Data<Vec<VersionedProposal>>> {
+        let result: Result<Data<Vec<VersionedProposal>>, _> = self
+            .contract
+            .call_function("get_proposals", json!({ "ids": proposal_ids }))
+            .map_err(|e| Error::Rpc(e.to_string()))?
+            .read_only()
+            .at(block)
+            .fetch_from(&self.network)
+            .await;
+
+        result.map_err(query_error)
+    }
+
+    async fn get_all_proposal_ids(&self) -> Result<Data<Vec<i32>>> {
         let result: Result<Data<Vec<i32>>, _> = self
             .contract
             .call_function("get_all_proposal_ids", ())
-            .unwrap()
+            .map_err(|e| Error::Rpc(e.to_string()))?
             .read_only()
             .fetch_from(&self.network)
             .await;
 
-        match result {
-            Ok(res) => Ok(res.data),
-            Err(e) => {
-                println!("Error fetching proposal ids: {:?}", e);
-                Err(rocket::http::Status::InternalServerError)
-            }
-        }
+        result.map_err(query_error)
+    }
+
+    async fn get_rfps(
+        &self,
+        rfp_ids: &[i32],
+        block: BlockReference,
+    ) -> Result<Data<Vec<VersionedRFP>>> {
+        let result: Result<Data<Vec<VersionedRFP>>, _> = self
+            .contract
+            .call_function("get_rfps", json!({ "ids": rfp_ids }))
+            .map_err(|e| Error::Rpc(e.to_string()))?
+            .read_only()
+            .at(block)
+            .fetch_from(&self.network)
+            .await;
+
+        result.map_err(query_error)
+    }
+
+    async fn get_all_rfp_ids(&self) -> Result<Data<Vec<i32>>> {
+        let result: Result<Data<Vec<i32>>, _> = self
+            .contract
+            .call_function("get_all_rfp_ids", ())
+            .map_err(|e| Error::Rpc(e.to_string()))?
+            .read_only()
+            .fetch_from(&self.network)
+            .await;
+
+        result.map_err(query_error)
     }
 }
@@ -0,0 +1,217 @@
+use super::{not_found_on_panic, ContractReader, TransactionSource};
+use crate::community::{AddOn, Community};
+use crate::error::{Error, Result};
+use crate::nearblocks_client::types::{PageItem, Transaction};
+use devhub_shared::proposal::VersionedProposal;
+use devhub_shared::rfp::VersionedRFP;
+use near_account_id::AccountId;
+use near_api::types::Data;
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::{BlockId, BlockReference};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::path::Path;
+use std::sync::Arc;
+
+// Recorded nearblocks and RPC responses, e.g.
+//
+// {
+//   "transactions": [<txns as returned by nearblocks>],
+//   "calls": [
+//     {
+//       "contract": "devhub.near",
+//       "method": "get_proposal",
+//       "args": { "proposal_id": 1 },
+//       "block_height": 130000000,
+//       "result": <what the contract returned>
+//     }
+//   ]
+// }
+//
+// A call recorded with `"error"` instead of `"result"` fails like a contract panic.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Fixtures {
+    // Decoded when they are read, like the transactions of a nearblocks page
+    #[serde(default)]
+    pub transactions: Vec<Value>,
+    #[serde(default)]
+    pub calls: Vec<RecordedCall>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RecordedCall {
+    pub contract: String,
+    pub method: String,
+    #[serde(default)]
+    pub args: Value,
+    pub block_height: u64,
+    #[serde(default)]
+    pub result: Value,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+impl Fixtures {
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let file = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&file)?)
+    }
+
+    pub fn contract_reader(self: &Arc<Self>, contract: &AccountId) -> FixtureContractReader {
+        FixtureContractReader {
+            fixtures: self.clone(),
+            contract: contract.clone(),
+        }
+    }
+}
+
+// Ignores the cursor and returns everything since `since`, like nearblocks returns the whole
+// day without one, so the indexer has to skip what it processed already
+#[rocket::async_trait]
+impl TransactionSource for Fixtures {
+    async fn account_txns(
+        &self,
+        contract: &AccountId,
+        method: Option<String>,
+        since: Option<i64>,
+        _cursor: Option<String>,
+    ) -> anyhow::Result<Vec<PageItem>> {
+        let mut transactions: Vec<&Value> = self
+            .transactions
+            .iter()
+            .filter(|raw| raw["receiver_account_id"] == contract.as_str())
+            .filter(|raw| match &method {
+                Some(method) => raw["actions"][0]["method"] == method.as_str(),
+                None => true,
+            })
+            .filter(|raw| match since {
+                Some(since) => {
+                    raw["block_timestamp"]
+                        .as_str()
+                        .and_then(|ts| ts.parse::<i64>().ok())
+                        .unwrap_or_default()
+                        >= since
+                }
+                None => true,
+            })
+            .collect();
+
+        // Stable, so receipts of one block keep their recorded order
+        transactions.sort_by_key(|raw| raw["block"]["block_height"].as_i64());
+
+        Ok(transactions
+            .into_iter()
+            .cloned()
+            .map(Transaction::decode_item)
+            .collect())
+    }
+}
+
+// Answers the view calls of one contract from the recorded calls
+pub struct FixtureContractReader {
+    fixtures: Arc<Fixtures>,
+    contract: AccountId,
+}
+
+impl FixtureContractReader {
+    // The latest call recorded at or before `block` with exactly these args
+    fn call<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        args: Value,
+        block: BlockReference,
+    ) -> Result<Data<T>> {
+        let max_height = match block {
+            BlockReference::BlockId(BlockId::Height(height)) => height,
+            _ => u64::MAX,
+        };
+
+        let call = self
+            .fixtures
+            .calls
+            .iter()
+            .filter(|call| {
+                call.contract == self.contract.as_str()
+                    && call.method == method
+                    && call.args == args
+                    && call.block_height <= max_height
+            })
+            .max_by_key(|call| call.block_height)
+            .ok_or_else(|| {
+                Error::Rpc(format!(
+                    "No recorded {} call of {} with {}",
+                    method, self.contract, args
+                ))
+            })?;
+
+        if let Some(error) = &call.error {
+            return Err(Error::Contract(error.clone()));
+        }
+
+        let data = serde_json::from_value(call.result.clone())
+            .map_err(|e| Error::Decode(format!("Invalid recorded {} result: {}", method, e)))?;
+
+        Ok(Data {
+            data,
+            block_height: call.block_height,
+            block_hash: CryptoHash::default(),
+        })
+    }
+}
+
+#[rocket::async_trait]
+impl ContractReader for FixtureContractReader {
+    async fn get_proposal_at(
+        &self,
+        proposal_id: i32,
+        block: BlockReference,
+    ) -> Result<Data<VersionedProposal>> {
+        self.call("get_proposal", json!({ "proposal_id": proposal_id }), block)
+            .map_err(|e| not_found_on_panic(e, "Proposal", proposal_id))
+    }
+
+    async fn get_proposals(
+        &self,
+        proposal_ids: &[i32],
+        block: BlockReference,
+    ) -> Result<Data<Vec<VersionedProposal>>> {
+        self.call("get_proposals", json!({ "ids": proposal_ids }), block)
+    }
+
+    async fn get_all_proposal_ids(&self) -> Result<Data<Vec<i32>>> {
+        self.call("get_all_proposal_ids", json!({}), BlockReference::latest())
+    }
+
+    async fn get_rfp_at(&self, rfp_id: i32, block: BlockReference) -> Result<VersionedRFP> {
+        self.call("get_rfp", json!({ "rfp_id": rfp_id }), block)
+            .map(|res| res.data)
+            .map_err(|e| not_found_on_panic(e, "RFP", rfp_id))
+    }
+
+    async fn get_rfps(
+        &self,
+        rfp_ids: &[i32],
+        block: BlockReference,
+    ) -> Result<Data<Vec<VersionedRFP>>> {
+        self.call("get_rfps", json!({ "ids": rfp_ids }), block)
+    }
+
+    async fn get_all_rfp_ids(&self) -> Result<Data<Vec<i32>>> {
+        self.call("get_all_rfp_ids", json!({}), BlockReference::latest())
+    }
+
+    async fn get_community_at(
+        &self,
+        handle: &str,
+        block: BlockReference,
+    ) -> Result<Option<Community>> {
+        self.call("get_community", json!({ "handle": handle }), block)
+            .map(|res| res.data)
+    }
+
+    async fn get_addon_at(&self, addon_id: &str, block: BlockReference) -> Result<Option<AddOn>> {
+        self.call("get_addon", json!({ "id": addon_id }), block)
+            .map(|res| res.data)
+    }
+}
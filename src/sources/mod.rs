@@ -0,0 +1,175 @@
+use crate::community::{AddOn, Community};
+use crate::config::Config;
+use crate::error::{Error, Result};
+use crate::nearblocks_client::types::PageItem;
+use crate::rpc_service::RpcService;
+use devhub_shared::proposal::VersionedProposal;
+use devhub_shared::rfp::VersionedRFP;
+use near_account_id::AccountId;
+use near_api::types::Data;
+use near_primitives::types::{BlockId, BlockReference};
+use rocket::fairing::AdHoc;
+use std::sync::Arc;
+
+pub mod fixtures;
+
+use fixtures::Fixtures;
+
+// Where the indexer reads the receipts of a contract from, nearblocks when running
+// and recorded responses in tests, see `Fixtures`
+// `async_trait` marks the boxed futures it returns `#[must_use]` again
+#[allow(clippy::double_must_use)]
+#[rocket::async_trait]
+pub trait TransactionSource: Send + Sync {
+    // Receipts of `contract` calling `method`, or any method without one, oldest first.
+    // May start earlier than `since` (a block timestamp in nanoseconds) but never later.
+    // With a `cursor`, the `id` of a transaction returned before, it starts right after it.
+    // Transactions that don't decode are returned as they are, so they can be quarantined.
+    async fn account_txns(
+        &self,
+        contract: &AccountId,
+        method: Option<String>,
+        since: Option<i64>,
+        cursor: Option<String>,
+    ) -> anyhow::Result<Vec<PageItem>>;
+}
+
+// The view methods of one DevHub contract, read through near-api when running
+#[allow(clippy::double_must_use)]
+#[rocket::async_trait]
+pub trait ContractReader: Send + Sync {
+    // Also returns the block height the proposal was read at
+    async fn get_proposal(&self, proposal_id: i32) -> Result<Data<VersionedProposal>> {
+        self.get_proposal_at(proposal_id, BlockReference::latest())
+            .await
+    }
+
+    // Reads the proposal as it was at `block`, old blocks need an archival RPC
+    async fn get_proposal_at(
+        &self,
+        proposal_id: i32,
+        block: BlockReference,
+    ) -> Result<Data<VersionedProposal>>;
+
+    // Reads a batch of proposals at one block, so a backfill sees a consistent state
+    async fn get_proposals(
+        &self,
+        proposal_ids: &[i32],
+        block: BlockReference,
+    ) -> Result<Data<Vec<VersionedProposal>>>;
+
+    // Also returns the block height the ids were read at
+    async fn get_all_proposal_ids(&self) -> Result<Data<Vec<i32>>>;
+
+    async fn get_rfp(&self, rfp_id: i32) -> Result<VersionedRFP> {
+        self.get_rfp_at(rfp_id, BlockReference::latest()).await
+    }
+
+    // Reads the RFP as it was at `block`, old blocks need an archival RPC
+    async fn get_rfp_at(&self, rfp_id: i32, block: BlockReference) -> Result<VersionedRFP>;
+
+    async fn get_rfps(
+        &self,
+        rfp_ids: &[i32],
+        block: BlockReference,
+    ) -> Result<Data<Vec<VersionedRFP>>>;
+
+    async fn get_all_rfp_ids(&self) -> Result<Data<Vec<i32>>>;
+
+    // Reads the community as it was at `block`, `None` once it has been deleted
+    async fn get_community_at(
+        &self,
+        handle: &str,
+        block: BlockReference,
+    ) -> Result<Option<Community>>;
+
+    // Reads the addon as it was at `block`
+    async fn get_addon_at(&self, addon_id: &str, block: BlockReference) -> Result<Option<AddOn>>;
+}
+
+// The state right after all receipts of the block at `block_height` were executed
+pub fn at_block_height(block_height: i64) -> BlockReference {
+    BlockReference::BlockId(BlockId::Height(block_height as u64))
+}
+
+// `get_proposal` and `get_rfp` only panic for ids the contract doesn't know
+pub fn not_found_on_panic(e: Error, kind: &str, id: i32) -> Error {
+    match e {
+        Error::Contract(_) => Error::NotFound(format!("{} {} not found", kind, id)),
+        e => e,
+    }
+}
+
+type ContractReaderFactory = dyn Fn(&AccountId) -> Arc<dyn ContractReader> + Send + Sync;
+
+// The sources the indexer and the routes read from, kept in managed state
+#[derive(Clone)]
+pub struct Sources {
+    transactions: Arc<dyn TransactionSource>,
+    contract_readers: Arc<ContractReaderFactory>,
+}
+
+impl Sources {
+    pub fn new<F>(transactions: Arc<dyn TransactionSource>, contract_readers: F) -> Self
+    where
+        F: Fn(&AccountId) -> Arc<dyn ContractReader> + Send + Sync + 'static,
+    {
+        Self {
+            transactions,
+            contract_readers: Arc::new(contract_readers),
+        }
+    }
+
+    // nearblocks and the RPC of the configured network
+    pub fn live(config: &Config) -> Self {
+        let network = config.network_config();
+        Self::new(Arc::new(config.nearblocks_client()), move |contract| {
+            Arc::new(RpcService::new(network.clone(), contract.clone()))
+        })
+    }
+
+    // Recorded responses only, nothing goes over the network
+    pub fn fixtures(fixtures: Fixtures) -> Self {
+        let fixtures = Arc::new(fixtures);
+        Self::new(fixtures.clone(), move |contract| {
+            Arc::new(fixtures.contract_reader(contract))
+        })
+    }
+
+    pub fn transactions(&self) -> &dyn TransactionSource {
+        self.transactions.as_ref()
+    }
+
+    pub fn contract_reader(&self, contract: &AccountId) -> Arc<dyn ContractReader> {
+        (self.contract_readers)(contract)
+    }
+}
+
+// Puts `Sources` in managed state, needs `config::stage()` to be attached first
+pub fn stage() -> AdHoc {
+    AdHoc::try_on_ignite("Sources", |rocket| async {
+        let config = match rocket.state::<Config>() {
+            Some(config) => config,
+            None => {
+                rocket::error!("`Sources` needs the config, attach `config::stage()` first");
+                return Err(rocket);
+            }
+        };
+
+        let sources = match &config.fixtures {
+            Some(path) => match Fixtures::from_file(path) {
+                Ok(fixtures) => {
+                    rocket::info!("Reading recorded responses from {}", path.display());
+                    Sources::fixtures(fixtures)
+                }
+                Err(e) => {
+                    rocket::error!("Failed to load fixtures {}: {}", path.display(), e);
+                    return Err(rocket);
+                }
+            },
+            None => Sources::live(config),
+        };
+
+        Ok(rocket.manage(sources))
+    })
+}
@@ -1,10 +1,22 @@
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
-use crate::db::types::ProposalWithLatestSnapshotView;
+use crate::db::types::{
+    AuthorEditRecord, AuthorSummary, CommunitySnapshotRecord, FailedReceiptRecord,
+    ProposalSearchResult, ProposalSnapshotRecord, ProposalWithLatestSnapshotView,
+    RfpWithLatestSnapshotView,
+};
 
 #[derive(Clone, Debug, Serialize, Deserialize, Default, ToSchema)]
-#[aliases(PaginatedProposalResponse = PaginatedResponse<ProposalWithLatestSnapshotView>)]
+#[aliases(
+    PaginatedProposalResponse = PaginatedResponse<ProposalWithLatestSnapshotView>,
+    PaginatedProposalSnapshotResponse = PaginatedResponse<ProposalSnapshotRecord>,
+    PaginatedProposalSearchResponse = PaginatedResponse<ProposalSearchResult>,
+    PaginatedRfpResponse = PaginatedResponse<RfpWithLatestSnapshotView>,
+    PaginatedAuthorResponse = PaginatedResponse<AuthorSummary>,
+    PaginatedCommunityResponse = PaginatedResponse<CommunitySnapshotRecord>,
+    PaginatedFailedReceiptResponse = PaginatedResponse<FailedReceiptRecord>
+)]
 pub struct PaginatedResponse<T: Serialize> {
     pub records: Vec<T>,
     pub page: u64,
@@ -15,8 +27,12 @@ pub struct PaginatedResponse<T: Serialize> {
 
 impl<T: Serialize> PaginatedResponse<T> {
     pub fn new(records: Vec<T>, page: u64, limit: u64, total_records: u64) -> Self {
-        let extra_page = if total_records % limit == 0 { 0 } else { 1 };
-        let total_pages = (total_records / limit) + extra_page;
+        // A page size of 0 has no pages to go through
+        let total_pages = if limit == 0 {
+            0
+        } else {
+            total_records.div_ceil(limit)
+        };
         Self {
             records,
             page,
@@ -25,6 +41,36 @@ impl<T: Serialize> PaginatedResponse<T> {
             total_records,
         }
     }
+
+    // The page read at `offset` with `limit` out of `total` matching records
+    pub fn from_offset(records: Vec<T>, offset: i64, limit: i64, total: i64) -> Self {
+        let (limit, offset) = clamp_page(Some(limit), Some(offset));
+        Self::new(
+            records,
+            (offset / limit + 1) as u64,
+            limit as u64,
+            total.max(0) as u64,
+        )
+    }
+}
+
+// The `limit` and `offset` of the listing routes, 25 records from the start by default.
+// A page holds at least one record and starts at or after the first one.
+pub fn clamp_page(limit: Option<i64>, offset: Option<i64>) -> (i64, i64) {
+    (limit.unwrap_or(25).max(1), offset.unwrap_or(0).max(0))
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct AuthorResponse {
+    pub author_id: String,
+    pub proposals: Vec<ProposalWithLatestSnapshotView>,
+    pub rfps: Vec<RfpWithLatestSnapshotView>,
+    // Most recent snapshots edited by the account
+    pub edits: Vec<AuthorEditRecord>,
+    // Proposals where the account is the supervisor
+    pub supervisor_of: Vec<i32>,
+    // Proposals where the account is the requested sponsor
+    pub requested_sponsor_of: Vec<i32>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
@@ -54,3 +100,39 @@ pub struct ProposalResponse {
     pub timeline: Option<serde_json::Value>,
     pub views: Option<i32>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_pages_from_one() {
+        let page = PaginatedResponse::from_offset(vec![1, 2], 50, 25, 52);
+
+        assert_eq!(page.page, 3);
+        assert_eq!(page.limit, 25);
+        assert_eq!(page.total_pages, 3);
+        assert_eq!(page.total_records, 52);
+    }
+
+    #[test]
+    fn reads_at_least_one_record_per_page() {
+        assert_eq!(clamp_page(Some(0), Some(-5)), (1, 0));
+
+        let page = PaginatedResponse::from_offset(vec![1], 3, 0, 10);
+
+        assert_eq!(page.page, 4);
+        assert_eq!(page.limit, 1);
+        assert_eq!(page.total_pages, 10);
+    }
+
+    #[test]
+    fn keeps_counting_pages_past_the_end() {
+        let page = PaginatedResponse::<i32>::from_offset(vec![], 100, 25, 30);
+
+        assert!(page.records.is_empty());
+        assert_eq!(page.page, 5);
+        assert_eq!(page.total_pages, 2);
+        assert_eq!(page.total_records, 30);
+    }
+}
@@ -0,0 +1,274 @@
+// Runs the indexer against the recorded responses in `tests/fixtures`. Needs a Postgres
+// server in `DATABASE_URL`, every test gets its own database with the migrations applied.
+
+use devhub_cache_api::api_background_service::fetch_unprocessed_txns;
+use devhub_cache_api::db::types::{CommunitySnapshotRecord, DumpRecord};
+use devhub_cache_api::db::DB;
+use devhub_cache_api::entrypoints::{communities, proposal, rfp};
+use devhub_cache_api::error::Error;
+use devhub_cache_api::sources::fixtures::Fixtures;
+use devhub_cache_api::sources::Sources;
+use near_account_id::AccountId;
+use serde_json::json;
+use sqlx::PgPool;
+use std::path::Path;
+
+const CONTRACT: &str = "devhub.near";
+
+fn sources() -> Sources {
+    let fixtures = Fixtures::from_file(Path::new("tests/fixtures/sync.json")).unwrap();
+    Sources::fixtures(fixtures)
+}
+
+async fn sync(db: &DB, sources: &Sources) {
+    let contract: AccountId = CONTRACT.parse().unwrap();
+    let transactions = fetch_unprocessed_txns(
+        db,
+        sources.transactions(),
+        CONTRACT,
+        &[
+            proposal::PROPOSAL_METHOD_FAMILY,
+            rfp::RFP_METHOD_FAMILY,
+            communities::COMMUNITY_METHOD_FAMILY,
+        ],
+    )
+    .await
+    .unwrap();
+
+    proposal::sync_proposals(db, sources, &contract, &transactions)
+        .await
+        .unwrap();
+    rfp::sync_rfps(db, sources, &contract, &transactions)
+        .await
+        .unwrap();
+    communities::sync_communities(db, sources, &contract, &transactions)
+        .await
+        .unwrap();
+}
+
+#[sqlx::test(migrations = "./migrations")]
+async fn replays_proposal_edits(pool: PgPool) {
+    let db = DB::from(pool);
+
+    sync(&db, &sources()).await;
+
+    let (snapshots, total) = db
+        .get_proposal_snapshots(CONTRACT, 1, 10, "asc", 0, None, None, None)
+        .await
+        .unwrap();
+    // The failed edit at block 250 isn't replayed
+    assert_eq!(total, 4);
+    let blocks: Vec<_> = snapshots.iter().map(|s| s.block_height).collect();
+    assert_eq!(blocks, vec![100, 200, 300, 500]);
+    let editors: Vec<_> = snapshots.iter().map(|s| s.editor_id.as_str()).collect();
+    assert_eq!(
+        editors,
+        vec!["alice.near", "alice.near", "moderator.near", "bob.near"]
+    );
+
+    let edited = &snapshots[1];
+    assert_eq!(edited.name.as_deref(), Some("Edited proposal"));
+    assert_eq!(edited.proposal_body_version, "V2");
+    assert_eq!(edited.labels, json!(["a", "b"]));
+
+    let reviewed = &snapshots[2];
+    assert_eq!(reviewed.name.as_deref(), Some("Edited proposal"));
+    assert_eq!(reviewed.timeline.as_ref().unwrap()["status"], "REVIEW");
+
+    // Both edits of block 500 share a snapshot, the later one wins
+    let latest = &snapshots[3];
+    assert_eq!(latest.name.as_deref(), Some("Second edit in block 500"));
+}
+
+#[sqlx::test(migrations = "./migrations")]
+async fn reads_rfp_edits_from_the_contract(pool: PgPool) {
+    let db = DB::from(pool);
+
+    sync(&db, &sources()).await;
+
+    // Nearblocks returned the args of its callback base64 encoded
+    let created = db
+        .get_latest_rfp_snapshot(CONTRACT, 1, Some(1730000150000000000))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(created.name.as_deref(), Some("RFP"));
+    assert_eq!(created.block_height, 150);
+    assert_eq!(created.editor_id, "carol.near");
+
+    let latest = db
+        .get_latest_rfp_snapshot(CONTRACT, 1, None)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(latest.block_height, 400);
+    assert_eq!(latest.name.as_deref(), Some("Edited RFP"));
+    assert_eq!(latest.timeline, Some(json!({ "status": "EVALUATION" })));
+}
+
+#[sqlx::test(migrations = "./migrations")]
+async fn dumps_every_proposal_receipt(pool: PgPool) {
+    let db = DB::from(pool);
+
+    sync(&db, &sources()).await;
+
+    let dumps: Vec<DumpRecord> = db.get_all_dumps(CONTRACT).await.unwrap();
+    let receipts: Vec<_> = dumps
+        .iter()
+        .map(|d| (d.receipt_id.as_str(), d.proposal_id, d.succeeded))
+        .collect();
+    // `add_proposal` doesn't know its id yet, the failed edit at block 250 is kept too
+    assert_eq!(
+        receipts,
+        vec![
+            ("r0", None, true),
+            ("r1", Some(1), true),
+            ("r3", Some(1), true),
+            ("r4", Some(1), false),
+            ("r5", Some(1), true),
+            ("r7", Some(1), true),
+            ("r8", Some(1), true),
+        ]
+    );
+}
+
+#[sqlx::test(migrations = "./migrations")]
+async fn filters_rfps_by_any_of_the_stages(pool: PgPool) {
+    let db = DB::from(pool);
+
+    sync(&db, &sources()).await;
+
+    let stages = |stages: &[&str]| Some(stages.iter().map(|s| s.to_string()).collect());
+    let (_, total) = db
+        .get_rfps_with_latest_snapshot(
+            CONTRACT,
+            10,
+            "desc",
+            0,
+            None,
+            None,
+            stages(&["CANCELLED", "EVALUATION"]),
+        )
+        .await
+        .unwrap();
+    assert_eq!(total, 1);
+
+    let (_, total) = db
+        .get_rfps_with_latest_snapshot(CONTRACT, 10, "desc", 0, None, None, stages(&["CANCELLED"]))
+        .await
+        .unwrap();
+    assert_eq!(total, 0);
+}
+
+#[sqlx::test(migrations = "./migrations")]
+async fn advances_the_sync_cursors(pool: PgPool) {
+    let db = DB::from(pool);
+    let sources = sources();
+
+    sync(&db, &sources).await;
+
+    for method_family in [
+        proposal::PROPOSAL_METHOD_FAMILY,
+        rfp::RFP_METHOD_FAMILY,
+        communities::COMMUNITY_METHOD_FAMILY,
+    ] {
+        let cursor = db
+            .get_sync_cursor(CONTRACT, method_family)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(cursor.block_height, 700, "{}", method_family);
+        assert_eq!(cursor.block_timestamp, 1730000700000000000);
+        assert_eq!(cursor.receipt_id.as_deref(), Some("r11"));
+        assert_eq!(cursor.nearblocks_cursor.as_deref(), Some("r11"));
+    }
+
+    // Nothing after the cursors, so another sync doesn't store anything
+    sync(&db, &sources).await;
+
+    let (_, total) = db
+        .get_proposal_snapshots(CONTRACT, 1, 10, "asc", 0, None, None, None)
+        .await
+        .unwrap();
+    assert_eq!(total, 4);
+}
+
+#[sqlx::test(migrations = "./migrations")]
+async fn skips_the_receipts_before_the_cursor_in_its_block(pool: PgPool) {
+    let db = DB::from(pool);
+    let sources = sources();
+
+    // The fixtures return the block of the cursor again, like nearblocks returns its day
+    for _ in 0..2 {
+        sync(&db, &sources).await;
+    }
+
+    let latest = db
+        .get_latest_proposal_snapshot(CONTRACT, 1, None)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(latest.name.as_deref(), Some("Second edit in block 500"));
+    assert_eq!(latest.editor_id, "bob.near");
+
+    let cursor = db
+        .get_sync_cursor(CONTRACT, proposal::PROPOSAL_METHOD_FAMILY)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(cursor.receipt_id.as_deref(), Some("r11"));
+}
+
+#[sqlx::test(migrations = "./migrations")]
+async fn reads_communities_at_the_block_of_the_receipt(pool: PgPool) {
+    let db = DB::from(pool.clone());
+
+    sync(&db, &sources()).await;
+
+    let snapshots: Vec<CommunitySnapshotRecord> =
+        sqlx::query_as("SELECT * FROM community_snapshots WHERE contract = $1 ORDER BY ts")
+            .bind(CONTRACT)
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+    // The failed update at block 650 isn't stored
+    let blocks: Vec<_> = snapshots.iter().map(|s| s.block_height).collect();
+    assert_eq!(blocks, vec![600, 700]);
+    let names: Vec<_> = snapshots.iter().map(|s| s.name.as_deref()).collect();
+    assert_eq!(names, vec![Some("Devs"), Some("Developers")]);
+}
+
+#[sqlx::test(migrations = "./migrations")]
+async fn keeps_constraint_violations_as_permanent(pool: PgPool) {
+    // A NULL contract breaks the NOT NULL constraint (SQLSTATE 23502)
+    let violation = sqlx::query("INSERT INTO failed_receipts (receipt_id) VALUES ('r1')")
+        .execute(&pool)
+        .await
+        .unwrap_err();
+    assert!(Error::from(violation).is_permanent());
+
+    // Dividing by zero is a data exception (SQLSTATE 22012)
+    let data = sqlx::query("SELECT 1 / 0")
+        .execute(&pool)
+        .await
+        .unwrap_err();
+    assert!(Error::from(data).is_permanent());
+
+    pool.close().await;
+    let closed = sqlx::query("SELECT 1").execute(&pool).await.unwrap_err();
+    assert!(!Error::from(closed).is_permanent());
+}
+
+#[sqlx::test(migrations = "./migrations")]
+async fn ranks_proposals_matching_only_the_timeline(pool: PgPool) {
+    let db = DB::from(pool);
+
+    sync(&db, &sources()).await;
+
+    let (results, total) = db
+        .search_proposals_with_latest_snapshot(CONTRACT, "draft", 10, 0)
+        .await
+        .unwrap();
+    assert_eq!(total, 1);
+    assert!(results[0].rank > 0.0);
+}